@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::{App, Arg};
 use std::fs;
-use ue_object_reader::to_json;
+use ue_object_reader::{error::ParseError, json, to_json};
 
 pub fn main() -> Result<()> {
     let matches = App::new("k2node to json")
@@ -16,9 +16,14 @@ pub fn main() -> Result<()> {
             Arg::with_name("output")
                 .long("output")
                 .short("o")
-                .takes_value(false)
+                .takes_value(true)
                 .default_value("output.json"),
         )
+        .arg(
+            Arg::with_name("schema")
+                .long("schema")
+                .help("Writes the JSON Schema of the output instead of converting a file"),
+        )
         .arg(
             Arg::with_name("pretty")
                 .long("pretty")
@@ -27,8 +32,15 @@ pub fn main() -> Result<()> {
         )
         .get_matches();
 
-    let in_file = matches.value_of("input").unwrap();
     let out_file = matches.value_of("output").unwrap();
+    if matches.is_present("schema") {
+        let mut schema = serde_json::to_string_pretty(&json::schema())?;
+        schema.push('\n');
+        fs::write(out_file, schema)?;
+        return Ok(());
+    }
+
+    let in_file = matches.value_of("input").unwrap();
     let pretty = matches.is_present("pretty");
 
     let graph_code = match fs::read_to_string(in_file) {
@@ -36,7 +48,15 @@ pub fn main() -> Result<()> {
         Err(err) => panic!("Failed to open the file : {:?}", err),
     };
 
-    let j = to_json(&graph_code, pretty)?;
+    let j = match to_json(&graph_code, pretty) {
+        Ok(j) => j,
+        Err(err) => {
+            if let Some(e) = err.downcast_ref::<ParseError>() {
+                eprint!("{}", e.snippet());
+            }
+            return Err(err);
+        }
+    };
     fs::write(out_file, j)?;
 
     Ok(())
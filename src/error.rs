@@ -0,0 +1,110 @@
+use nom::Offset;
+use thiserror::Error;
+
+/// An error that describes where and why parsing of the serialized text failed.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("line {line}, column {column}: expected {expected}{}", in_object(.object))]
+pub struct ParseError {
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The column number in characters, starting at 1.
+    pub column: usize,
+    /// The name of the innermost object that was being parsed.
+    pub object: Option<String>,
+    /// A description of what the parser expected at this position.
+    pub expected: String,
+    /// The text of the line on which the error occurred.
+    pub line_text: String,
+}
+
+fn in_object(object: &Option<String>) -> String {
+    match object {
+        Some(name) => format!(" in object {}", name),
+        None => String::new(),
+    }
+}
+
+impl ParseError {
+    /// Creates an error located at `at`, which must be a slice of `source`.
+    pub fn new(
+        source: &str,
+        at: &str,
+        object: Option<String>,
+        expected: impl Into<String>,
+    ) -> Self {
+        let offset = source.offset(at);
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        ParseError {
+            line: source[..line_start].matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            object,
+            expected: expected.into(),
+            line_text: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_owned(),
+        }
+    }
+
+    /// Renders the offending line with a caret under the error position.
+    pub fn snippet(&self) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let caret: String = self
+            .line_text
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "error: expected {}{}\n{}--> line {}, column {}\n{} |\n{} | {}\n{} | {}^\n",
+            self.expected,
+            in_object(&self.object),
+            gutter,
+            self.line,
+            self.column,
+            gutter,
+            number,
+            self.line_text,
+            gutter,
+            caret
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_error() {
+        let source = "Begin Object\r\n\tX=?\r\nEnd Object";
+        let e = ParseError::new(
+            source,
+            &source[17..],
+            Some("K2Node_Knot_0".to_owned()),
+            "property value",
+        );
+        assert_eq!(e.line, 2);
+        assert_eq!(e.column, 4);
+        assert_eq!(e.line_text, "\tX=?");
+        assert_eq!(
+            e.to_string(),
+            "line 2, column 4: expected property value in object K2Node_Knot_0"
+        );
+        assert_eq!(
+            e.snippet(),
+            "error: expected property value in object K2Node_Knot_0\n --> line 2, column 4\n  |\n2 | \tX=?\n  | \t  ^\n"
+        );
+    }
+
+    #[test]
+    fn locate_error_at_end() {
+        let source = "Begin Object\n";
+        let e = ParseError::new(source, &source[source.len()..], None, "`End Object`");
+        assert_eq!((e.line, e.column), (2, 1));
+        assert_eq!(e.line_text, "");
+    }
+}
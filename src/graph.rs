@@ -0,0 +1,8 @@
+pub mod model;
+pub mod edge;
+pub mod pin_type;
+pub mod exec;
+pub mod data;
+pub mod knot;
+pub mod comment;
+pub mod pseudocode;
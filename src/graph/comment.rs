@@ -0,0 +1,210 @@
+use std::cmp::Reverse;
+
+use serde::Serialize;
+
+use super::model::{BlueprintGraph, Node, Position};
+use crate::parser::ast::PropValue;
+
+/// The class of comment boxes.
+pub const COMMENT_CLASS: &str = "EdGraphNode_Comment";
+
+/// A rectangle on the graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Rect {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// A comment box and the nodes inside it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommentBox {
+    /// The index of the comment node in the graph.
+    pub node: usize,
+    pub comment: String,
+    pub rect: Rect,
+    /// The indices of all nodes inside the box, including nested comment boxes and their nodes.
+    pub members: Vec<usize>,
+    /// The index of the smallest comment box this box is nested in.
+    pub parent: Option<usize>,
+}
+
+impl Rect {
+    /// Whether the point lies inside the rectangle, edges included.
+    pub fn contains(&self, p: Position) -> bool {
+        self.x <= p.x && p.x <= self.x + self.width && self.y <= p.y && p.y <= self.y + self.height
+    }
+
+    /// Whether the other rectangle lies entirely inside this one.
+    pub fn encloses(&self, other: &Rect) -> bool {
+        self.contains(Position {
+            x: other.x,
+            y: other.y,
+        }) && self.contains(Position {
+            x: other.x + other.width,
+            y: other.y + other.height,
+        })
+    }
+
+    fn area(&self) -> i64 {
+        self.width * self.height
+    }
+}
+
+impl Node {
+    /// Whether the node is a comment box.
+    pub fn is_comment_box(&self) -> bool {
+        self.class_name() == COMMENT_CLASS
+    }
+
+    /// Returns the rectangle of a comment box. Other nodes do not record their size.
+    pub fn comment_rect(&self) -> Option<Rect> {
+        if !self.is_comment_box() {
+            return None;
+        }
+        let size = |key| self.prop(key).and_then(PropValue::as_i64).unwrap_or(0);
+        Some(Rect {
+            x: self.position.x,
+            y: self.position.y,
+            width: size("NodeWidth"),
+            height: size("NodeHeight"),
+        })
+    }
+}
+
+impl BlueprintGraph {
+    /// Finds out which nodes lie inside each comment box.
+    ///
+    /// The text format only records the size of comment boxes, so a node belongs to a box when
+    /// its position, the top left corner, is inside the box. A comment box belongs to another
+    /// when it is entirely inside it; of two boxes with the same rectangle, the later one belongs
+    /// to the earlier one, so that the boxes always form a tree.
+    pub fn comment_boxes(&self) -> Vec<CommentBox> {
+        let rects: Vec<(usize, Rect)> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, n)| n.comment_rect().map(|r| (i, r)))
+            .collect();
+        let nested = |outer: usize, rect: &Rect, inner: usize, r: &Rect| {
+            outer != inner && rect.encloses(r) && (rect != r || outer < inner)
+        };
+        let inside = |outer: usize, rect: &Rect, node: usize| match self.nodes[node].comment_rect()
+        {
+            Some(r) => nested(outer, rect, node, &r),
+            None => rect.contains(self.nodes[node].position),
+        };
+        rects
+            .iter()
+            .map(|&(node, rect)| {
+                let members = (0..self.nodes.len())
+                    .filter(|&i| i != node && inside(node, &rect, i))
+                    .collect();
+                let parent = rects
+                    .iter()
+                    .filter(|&&(i, r)| nested(i, &r, node, &rect))
+                    .min_by_key(|&&(i, r)| (r.area(), Reverse(i)))
+                    .map(|&(i, _)| i);
+                CommentBox {
+                    node,
+                    comment: self.nodes[node].comment.clone().unwrap_or_default(),
+                    rect,
+                    members,
+                    parent,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_graph;
+
+    #[test]
+    fn find_comment_members() {
+        let graph = read_graph(include_str!("../../k2node_codes/test2.bp")).unwrap();
+        let boxes = graph.comment_boxes();
+        let members = |comment: &CommentBox| {
+            comment
+                .members
+                .iter()
+                .map(|&n| graph.nodes[n].name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(boxes.len(), 3);
+        assert_eq!(boxes[0].comment, "Update Physics Handle Position");
+        assert_eq!(
+            boxes[0].rect,
+            Rect {
+                x: 548,
+                y: 1853,
+                width: 912,
+                height: 294
+            }
+        );
+        assert_eq!(
+            members(&boxes[0]),
+            vec![
+                "K2Node_Event_455",
+                "K2Node_CallFunction_58629",
+                "K2Node_IfThenElse_90",
+                "K2Node_VariableGet_600",
+                "K2Node_ExecutionSequence_48"
+            ]
+        );
+        assert_eq!(members(&boxes[1]).len(), 6);
+        assert_eq!(
+            members(&boxes[2]),
+            vec![
+                "K2Node_CallFunction_3355",
+                "K2Node_CallFunction_1637",
+                "K2Node_CallFunction_1638",
+                "K2Node_DynamicCast_95",
+                "K2Node_CallFunction_4527",
+                "K2Node_IfThenElse_41"
+            ]
+        );
+        assert!(boxes.iter().all(|b| b.parent.is_none()));
+    }
+
+    #[test]
+    fn find_nested_comments() {
+        let comment = |name: &str, x, y, w, h| {
+            format!(
+                "Begin Object Class=/Script/UnrealEd.EdGraphNode_Comment Name=\"{}\"\n   NodePosX={}\n   NodePosY={}\n   NodeWidth={}\n   NodeHeight={}\n   NodeComment=\"{}\"\nEnd Object\n",
+                name, x, y, w, h, name
+            )
+        };
+        let code = [
+            comment("Outer", 0, 0, 1000, 1000),
+            comment("Inner", 100, 100, 400, 400),
+            comment("Overlapping", 800, 800, 400, 400),
+            "Begin Object Class=/Script/BlueprintGraph.K2Node_Knot Name=\"Knot\"\n   NodePosX=200\n   NodePosY=200\nEnd Object\n".to_owned(),
+        ]
+        .concat();
+        let graph = read_graph(&code).unwrap();
+        let boxes = graph.comment_boxes();
+        assert_eq!(boxes[0].members, vec![1, 3]);
+        assert_eq!(boxes[0].parent, None);
+        assert_eq!(boxes[1].members, vec![3]);
+        assert_eq!(boxes[1].parent, Some(0));
+        assert!(boxes[2].members.is_empty());
+        assert_eq!(boxes[2].parent, None);
+
+        let code = [
+            comment("First", 0, 0, 500, 500),
+            comment("Second", 0, 0, 500, 500),
+            "Begin Object Class=/Script/BlueprintGraph.K2Node_Knot Name=\"Knot\"\n   NodePosX=200\n   NodePosY=200\nEnd Object\n".to_owned(),
+        ]
+        .concat();
+        let graph = read_graph(&code).unwrap();
+        let boxes = graph.comment_boxes();
+        assert_eq!(boxes[0].members, vec![1, 2]);
+        assert_eq!(boxes[0].parent, None);
+        assert_eq!(boxes[1].members, vec![2]);
+        assert_eq!(boxes[1].parent, Some(0));
+    }
+}
@@ -0,0 +1,188 @@
+use serde::Serialize;
+
+use super::edge::{Edges, PinRef};
+use super::model::{BlueprintGraph, Node, PinDirection};
+use crate::parser::ast::{find_prop, PropValue};
+
+/// The kind of the node whose output pin provides a value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SourceKind {
+    /// A `K2Node_VariableGet` reading the named variable.
+    Variable(String),
+    /// A pure node, which is evaluated whenever its value is needed.
+    Pure,
+    /// A node with exec pins, whose outputs hold the values of its last execution.
+    Impure,
+}
+
+/// An output pin providing a value, together with the values the pure node behind it depends
+/// on. The inputs of variable reads and impure nodes are not followed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataSource {
+    pub output: PinRef,
+    pub kind: SourceKind,
+    pub inputs: Vec<DataInput>,
+}
+
+/// A data input pin and the value connected to it, if any.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataInput {
+    pub pin: PinRef,
+    pub source: Option<DataSource>,
+}
+
+impl Node {
+    /// Whether the node is evaluated on demand rather than executed, i.e. it is a pure function
+    /// call or it has no exec pins at all.
+    pub fn is_pure(&self) -> bool {
+        self.prop("bIsPureFunc").and_then(PropValue::as_bool) == Some(true)
+            || !self.pins.iter().any(|p| p.pin_type.is_exec())
+    }
+
+    /// Returns the name of the variable of a `VariableGet` or `VariableSet` node.
+    pub fn variable_name(&self) -> Option<&str> {
+        match self.prop("VariableReference") {
+            Some(PropValue::PropList(v)) => find_prop(v, "MemberName").and_then(PropValue::as_str),
+            _ => None,
+        }
+    }
+}
+
+impl DataInput {
+    /// Returns the names of the variables read to compute this input, in tree order.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.visit(&mut |s| {
+            if let SourceKind::Variable(name) = &s.kind {
+                names.push(name.as_str());
+            }
+        });
+        names
+    }
+
+    /// Returns the indices of the nodes the value of this input comes from, in tree order.
+    pub fn nodes(&self) -> Vec<usize> {
+        let mut nodes = Vec::new();
+        self.visit(&mut |s| nodes.push(s.output.node));
+        nodes
+    }
+
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a DataSource)) {
+        if let Some(source) = &self.source {
+            f(source);
+            for input in &source.inputs {
+                input.visit(f);
+            }
+        }
+    }
+}
+
+impl BlueprintGraph {
+    /// Builds the tree of pure nodes and variable reads that the value of the input pin `pin`
+    /// is computed from.
+    pub fn data_input(&self, pin: PinRef) -> DataInput {
+        self.data_input_in(&self.edges(), pin, &mut Vec::new())
+    }
+
+    /// Returns the data input pins of a node, i.e. its input pins other than exec pins.
+    pub fn data_inputs(&self, node: usize) -> impl Iterator<Item = PinRef> + '_ {
+        self.nodes[node]
+            .pins
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.direction == PinDirection::Input && !p.pin_type.is_exec())
+            .map(move |(pin, _)| PinRef { node, pin })
+    }
+
+    fn data_input_in(&self, edges: &Edges, pin: PinRef, path: &mut Vec<usize>) -> DataInput {
+        let source = edges.incoming(pin).next().map(|output| {
+            let node = &self.nodes[output.node];
+            let kind = match node.variable_name() {
+                Some(name) if node.class_name() == "K2Node_VariableGet" => {
+                    SourceKind::Variable(name.to_owned())
+                }
+                _ if node.is_pure() => SourceKind::Pure,
+                _ => SourceKind::Impure,
+            };
+            // Pure nodes cannot form cycles in a valid graph, but do not loop on broken input.
+            let inputs = if kind == SourceKind::Pure && !path.contains(&output.node) {
+                path.push(output.node);
+                let inputs = self
+                    .data_inputs(output.node)
+                    .map(|p| self.data_input_in(edges, p, path))
+                    .collect();
+                path.pop();
+                inputs
+            } else {
+                Vec::new()
+            };
+            DataSource {
+                output,
+                kind,
+                inputs,
+            }
+        });
+        DataInput { pin, source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_graph;
+
+    #[test]
+    fn build_data_input() {
+        let graph = read_graph(include_str!("../../k2node_codes/test2.bp")).unwrap();
+        let pin = |node: &str, pin: &str| {
+            let node = graph.node_index(node).unwrap();
+            PinRef {
+                node,
+                pin: graph.nodes[node]
+                    .pins
+                    .iter()
+                    .position(|p| p.name == pin)
+                    .unwrap(),
+            }
+        };
+        let names = |nodes: Vec<usize>| {
+            nodes
+                .into_iter()
+                .map(|n| graph.nodes[n].name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        let condition = graph.data_input(pin("K2Node_IfThenElse_90", "Condition"));
+        assert_eq!(condition.variables(), vec!["PhysicsHandleActive"]);
+        assert!(condition.source.unwrap().inputs.is_empty());
+
+        let hovered = graph.data_input(pin("K2Node_CallFunction_4527", "Hovered"));
+        assert_eq!(
+            names(hovered.nodes()),
+            vec![
+                "K2Node_CallFunction_1638",
+                "K2Node_CallFunction_1637",
+                "K2Node_CallFunction_3355"
+            ]
+        );
+        assert!(hovered.variables().is_empty());
+        let less_equal = hovered.source.unwrap();
+        assert_eq!(less_equal.kind, SourceKind::Pure);
+        assert_eq!(
+            less_equal
+                .inputs
+                .iter()
+                .map(|i| (graph.pin(i.pin).name.as_str(), i.source.is_some()))
+                .collect::<Vec<_>>(),
+            vec![("self", false), ("A", true), ("B", false)]
+        );
+        let distance = less_equal.inputs[1].source.as_ref().unwrap();
+        assert_eq!(
+            distance.inputs[1].source.as_ref().unwrap().kind,
+            SourceKind::Impure
+        );
+
+        let b = graph.data_input(pin("K2Node_CallFunction_1638", "B"));
+        assert_eq!(b.source, None);
+    }
+}
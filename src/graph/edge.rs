@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use super::model::{BlueprintGraph, Link, Pin, PinDirection};
+
+/// A reference to a pin of a graph, by node index and pin index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct PinRef {
+    pub node: usize,
+    pub pin: usize,
+}
+
+/// A resolved connection between two pins. `from` is the output side of the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Edge {
+    pub from: PinRef,
+    pub to: PinRef,
+}
+
+/// A link whose target node or pin is not part of the graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DanglingLink {
+    pub from: PinRef,
+    pub link: Link,
+}
+
+/// The deduplicated connections of a graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Edges {
+    pub edges: Vec<Edge>,
+    pub dangling: Vec<DanglingLink>,
+}
+
+impl Edges {
+    /// Iterates over the pins connected to the output pin `from`.
+    pub fn outgoing(&self, from: PinRef) -> impl Iterator<Item = PinRef> + '_ {
+        self.edges
+            .iter()
+            .filter(move |e| e.from == from)
+            .map(|e| e.to)
+    }
+
+    /// Iterates over the pins connected to the input pin `to`.
+    pub fn incoming(&self, to: PinRef) -> impl Iterator<Item = PinRef> + '_ {
+        self.edges
+            .iter()
+            .filter(move |e| e.to == to)
+            .map(|e| e.from)
+    }
+
+    /// Iterates over the pins connected to `pin`, regardless of direction.
+    pub fn connected(&self, pin: PinRef) -> impl Iterator<Item = PinRef> + '_ {
+        self.outgoing(pin).chain(self.incoming(pin))
+    }
+}
+
+impl BlueprintGraph {
+    /// Returns the pin referenced by `pin`.
+    pub fn pin(&self, pin: PinRef) -> &Pin {
+        &self.nodes[pin.node].pins[pin.pin]
+    }
+
+    /// Finds the pin a link points to.
+    pub fn resolve_link(&self, link: &Link) -> Option<PinRef> {
+        let node = self.node_index(&link.node_name)?;
+        let pin = self.nodes[node]
+            .pins
+            .iter()
+            .position(|p| p.id == link.pin_id)?;
+        Some(PinRef { node, pin })
+    }
+
+    /// Resolves the `LinkedTo` lists of all pins into a list of edges.
+    ///
+    /// Both ends of a connection usually list each other, so every connection appears once in
+    /// the result, directed from the output pin to the input pin. Links whose target is not in
+    /// the graph, as happens when only a part of a graph is copied, are reported as dangling.
+    pub fn edges(&self) -> Edges {
+        let mut seen = HashSet::new();
+        let mut edges = Vec::new();
+        let mut dangling = Vec::new();
+        for (node, n) in self.nodes.iter().enumerate() {
+            for (pin, p) in n.pins.iter().enumerate() {
+                let from = PinRef { node, pin };
+                for link in &p.linked_to {
+                    let to = match self.resolve_link(link) {
+                        Some(to) => to,
+                        None => {
+                            dangling.push(DanglingLink {
+                                from,
+                                link: link.clone(),
+                            });
+                            continue;
+                        }
+                    };
+                    let edge = self.orient(from, to);
+                    if seen.insert(edge) {
+                        edges.push(edge);
+                    }
+                }
+            }
+        }
+        Edges { edges, dangling }
+    }
+
+    fn orient(&self, a: PinRef, b: PinRef) -> Edge {
+        let swap = match (self.pin(a).direction, self.pin(b).direction) {
+            (PinDirection::Input, PinDirection::Output) => true,
+            (PinDirection::Output, PinDirection::Input) => false,
+            _ => b < a,
+        };
+        if swap {
+            Edge { from: b, to: a }
+        } else {
+            Edge { from: a, to: b }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_graph;
+
+    #[test]
+    fn resolve_edges() {
+        let graph = read_graph(include_str!("../../k2node_codes/two_nodes.bp")).unwrap();
+        let edges = graph.edges();
+        let pin = |node: &str, pin: &str| {
+            let node = graph.node_index(node).unwrap();
+            PinRef {
+                node,
+                pin: graph.nodes[node]
+                    .pins
+                    .iter()
+                    .position(|p| p.name == pin)
+                    .unwrap(),
+            }
+        };
+        assert_eq!(
+            edges.edges,
+            vec![
+                Edge {
+                    from: pin("K2Node_InputAxisEvent_160", "then"),
+                    to: pin("K2Node_CallFunction_4678", "execute"),
+                },
+                Edge {
+                    from: pin("K2Node_InputAxisEvent_160", "AxisValue"),
+                    to: pin("K2Node_CallFunction_4678", "Val"),
+                },
+            ]
+        );
+        assert!(edges.dangling.is_empty());
+        assert_eq!(
+            edges
+                .incoming(pin("K2Node_CallFunction_4678", "Val"))
+                .collect::<Vec<_>>(),
+            vec![pin("K2Node_InputAxisEvent_160", "AxisValue")]
+        );
+        assert_eq!(
+            edges
+                .connected(pin("K2Node_InputAxisEvent_160", "then"))
+                .collect::<Vec<_>>(),
+            vec![pin("K2Node_CallFunction_4678", "execute")]
+        );
+    }
+
+    #[test]
+    fn report_dangling_links() {
+        let graph = read_graph(include_str!("../../k2node_codes/call_function.bp")).unwrap();
+        let edges = graph.edges();
+        assert!(edges.edges.is_empty());
+        assert_eq!(
+            edges
+                .dangling
+                .iter()
+                .map(|d| (graph.pin(d.from).name.as_str(), d.link.node_name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("execute", "K2Node_InputAxisEvent_160"),
+                ("Val", "K2Node_InputAxisEvent_160")
+            ]
+        );
+    }
+}
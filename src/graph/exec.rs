@@ -0,0 +1,224 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::Serialize;
+
+use super::edge::{Edges, PinRef};
+use super::model::{BlueprintGraph, PinDirection};
+
+/// The classes of the nodes from which execution starts.
+pub const ENTRY_CLASSES: [&str; 3] = [
+    "K2Node_Event",
+    "K2Node_CustomEvent",
+    "K2Node_InputAxisEvent",
+];
+
+/// How a node passes execution on to the nodes connected to its exec output pins. Each target
+/// is the exec input pin that is run next, or `None` if the output pin is not connected.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Flow {
+    /// A node with at most one exec output, which continues with `then`.
+    Next { then: Option<PinRef> },
+    /// `K2Node_IfThenElse`, which continues with `if_true` or `if_false` depending on the value
+    /// of its `Condition` pin.
+    Branch {
+        condition: Option<PinRef>,
+        if_true: Option<PinRef>,
+        if_false: Option<PinRef>,
+    },
+    /// `K2Node_ExecutionSequence`, which runs all of its outputs one after another.
+    Sequence(Vec<Option<PinRef>>),
+    /// Any other node with several exec outputs, which continues with one of them, e.g.
+    /// `then` or `CastFailed` of a cast.
+    Outputs(Vec<(String, Option<PinRef>)>),
+}
+
+/// A node reached by execution.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExecNode {
+    /// The index of the node in the graph.
+    pub node: usize,
+    pub flow: Flow,
+}
+
+/// The control-flow graph of the exec pins of a graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExecGraph {
+    /// The indices of the event nodes from which execution starts.
+    pub entries: Vec<usize>,
+    /// The nodes reachable from the entries, in the order they were found.
+    pub nodes: Vec<ExecNode>,
+}
+
+impl Flow {
+    /// Returns the exec input pins this flow may continue with.
+    pub fn targets(&self) -> Vec<PinRef> {
+        match self {
+            Flow::Next { then } => then.iter().copied().collect(),
+            Flow::Branch {
+                if_true, if_false, ..
+            } => if_true.iter().chain(if_false).copied().collect(),
+            Flow::Sequence(v) => v.iter().flatten().copied().collect(),
+            Flow::Outputs(v) => v.iter().filter_map(|(_, t)| *t).collect(),
+        }
+    }
+}
+
+impl ExecGraph {
+    /// Finds the exec node of the node with the given index.
+    pub fn node(&self, node: usize) -> Option<&ExecNode> {
+        self.nodes.iter().find(|n| n.node == node)
+    }
+
+    /// Returns the nodes that may run after `node`, depth first and in the order of the exec
+    /// outputs. Every node appears once, even when the flow loops back to it.
+    pub fn reachable(&self, node: usize) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut order = Vec::new();
+        let mut stack = vec![node];
+        while let Some(n) = stack.pop() {
+            if !seen.insert(n) {
+                continue;
+            }
+            if n != node {
+                order.push(n);
+            }
+            if let Some(e) = self.node(n) {
+                stack.extend(e.flow.targets().iter().rev().map(|t| t.node));
+            }
+        }
+        order
+    }
+}
+
+impl BlueprintGraph {
+    /// Builds the control-flow graph by following exec pins from every event node.
+    pub fn exec_graph(&self) -> ExecGraph {
+        let edges = self.edges();
+        let entries: Vec<usize> = (0..self.nodes.len())
+            .filter(|&n| ENTRY_CLASSES.contains(&self.nodes[n].class_name()))
+            .collect();
+        let mut seen = HashSet::new();
+        let mut nodes = Vec::new();
+        let mut queue: VecDeque<usize> = entries.iter().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            if !seen.insert(node) {
+                continue;
+            }
+            let flow = self.flow(&edges, node);
+            queue.extend(flow.targets().iter().map(|t| t.node));
+            nodes.push(ExecNode { node, flow });
+        }
+        ExecGraph { entries, nodes }
+    }
+
+    fn flow(&self, edges: &Edges, node: usize) -> Flow {
+        let n = &self.nodes[node];
+        let pin = |name: &str| {
+            n.pins
+                .iter()
+                .position(|p| p.name == name)
+                .map(|pin| PinRef { node, pin })
+        };
+        let target = |pin: Option<PinRef>| pin.and_then(|p| edges.outgoing(p).next());
+        let outputs: Vec<PinRef> = n
+            .pins
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.direction == PinDirection::Output && p.pin_type.is_exec())
+            .map(|(pin, _)| PinRef { node, pin })
+            .collect();
+        match n.class_name() {
+            "K2Node_IfThenElse" => Flow::Branch {
+                condition: pin("Condition"),
+                if_true: target(pin("then")),
+                if_false: target(pin("else")),
+            },
+            "K2Node_ExecutionSequence" => {
+                Flow::Sequence(outputs.into_iter().map(|p| target(Some(p))).collect())
+            }
+            _ if outputs.len() > 1 => Flow::Outputs(
+                outputs
+                    .into_iter()
+                    .map(|p| (self.pin(p).name.clone(), target(Some(p))))
+                    .collect(),
+            ),
+            _ => Flow::Next {
+                then: target(outputs.into_iter().next()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_graph;
+
+    #[test]
+    fn build_exec_graph() {
+        let graph = read_graph(include_str!("../../k2node_codes/test2.bp")).unwrap();
+        let exec = graph.exec_graph();
+        let index = |name: &str| graph.node_index(name).unwrap();
+        let name = |pin: &Option<PinRef>| pin.map(|p| graph.nodes[p.node].name.as_str());
+
+        assert_eq!(exec.entries, vec![index("K2Node_Event_455")]);
+        assert_eq!(exec.nodes.len(), 12);
+
+        match &exec
+            .node(index("K2Node_ExecutionSequence_48"))
+            .unwrap()
+            .flow
+        {
+            Flow::Sequence(v) => assert_eq!(
+                v.iter().map(name).collect::<Vec<_>>(),
+                vec![
+                    Some("K2Node_IfThenElse_90"),
+                    Some("K2Node_CallFunction_3355"),
+                    Some("K2Node_CallFunction_876")
+                ]
+            ),
+            f => panic!("not a sequence: {:?}", f),
+        }
+        match &exec.node(index("K2Node_IfThenElse_90")).unwrap().flow {
+            Flow::Branch {
+                condition,
+                if_true,
+                if_false,
+            } => {
+                assert_eq!(graph.pin(condition.unwrap()).name, "Condition");
+                assert_eq!(name(if_true), Some("K2Node_CallFunction_58629"));
+                assert_eq!(name(if_false), None);
+            }
+            f => panic!("not a branch: {:?}", f),
+        }
+        match &exec.node(index("K2Node_DynamicCast_95")).unwrap().flow {
+            Flow::Outputs(v) => assert_eq!(
+                v.iter()
+                    .map(|(pin, t)| (pin.as_str(), name(t)))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("then", Some("K2Node_CallFunction_4527")),
+                    ("CastFailed", None)
+                ]
+            ),
+            f => panic!("not a cast: {:?}", f),
+        }
+        assert_eq!(
+            exec.node(index("K2Node_CallFunction_1639")).unwrap().flow,
+            Flow::Next { then: None }
+        );
+        assert_eq!(exec.node(index("K2Node_VariableGet_600")), None);
+
+        assert_eq!(
+            exec.reachable(index("K2Node_CallFunction_876"))
+                .into_iter()
+                .map(|n| graph.nodes[n].name.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "K2Node_IfThenElse_170",
+                "K2Node_DynamicCast_46",
+                "K2Node_CallFunction_1639"
+            ]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use serde::Serialize;
+
+use super::model::{BlueprintGraph, Link, Node};
+
+/// The class of reroute nodes.
+pub const KNOT_CLASS: &str = "K2Node_Knot";
+
+/// A connection that passed through reroute nodes before they were collapsed, as seen from one
+/// of its ends. A connection between two pins of the graph has a route from either end.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KnotRoute {
+    /// The pin whose link was rewritten.
+    pub from: Link,
+    /// The pin at the other end of the knots, which may be outside the graph.
+    pub to: Link,
+    /// The knot pin `from` was connected to.
+    pub first: Link,
+    /// The knot pin `to` was connected to.
+    pub last: Link,
+    /// The names of the knots passed through, from `from` to `to`.
+    pub knots: Vec<String>,
+}
+
+/// What `collapse_knots` removed from a graph, to put it back with `insert_knots`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CollapsedKnots {
+    pub routes: Vec<KnotRoute>,
+    /// The rewritten pins with their links before collapsing.
+    pub links: Vec<(Link, Vec<Link>)>,
+    /// The removed knots with their indices in the graph before collapsing.
+    pub knots: Vec<(usize, Node)>,
+}
+
+impl Node {
+    /// Whether the node is a reroute node.
+    pub fn is_knot(&self) -> bool {
+        self.class_name() == KNOT_CLASS
+    }
+}
+
+impl BlueprintGraph {
+    /// Removes the reroute nodes and connects the pins on both sides of them directly, so that
+    /// the edges and the other analyses of the graph no longer see the knots.
+    ///
+    /// Links to knots are replaced in place. Knots that do not lead to a pin of another node are
+    /// dropped with their links. The links of every rewritten pin are kept, on whichever side of
+    /// the knots it is, so that `insert_knots` restores the original graph.
+    pub fn collapse_knots(&mut self) -> CollapsedKnots {
+        let mut routes = Vec::new();
+        let mut updates = Vec::new();
+        let mut originals = Vec::new();
+        for (node, n) in self.nodes.iter().enumerate() {
+            if n.is_knot() {
+                continue;
+            }
+            for (pin, p) in n.pins.iter().enumerate() {
+                if !p.linked_to.iter().any(|l| self.is_knot_link(l)) {
+                    continue;
+                }
+                let from = Link {
+                    node_name: n.name.clone(),
+                    pin_id: p.id,
+                };
+                let mut links = Vec::new();
+                for link in &p.linked_to {
+                    if !self.is_knot_link(link) {
+                        push_unique(&mut links, link.clone());
+                        continue;
+                    }
+                    for (end, last, knots) in self.through_knots(link, &mut Vec::new()) {
+                        routes.push(KnotRoute {
+                            from: from.clone(),
+                            to: end.clone(),
+                            first: link.clone(),
+                            last,
+                            knots,
+                        });
+                        push_unique(&mut links, end);
+                    }
+                }
+                originals.push((from, p.linked_to.clone()));
+                updates.push((node, pin, links));
+            }
+        }
+        for (node, pin, links) in updates {
+            self.nodes[node].pins[pin].set_linked_to(links);
+        }
+
+        let mut knots = Vec::new();
+        for (i, n) in std::mem::take(&mut self.nodes).into_iter().enumerate() {
+            if n.is_knot() {
+                knots.push((i, n));
+            } else {
+                self.nodes.push(n);
+            }
+        }
+        CollapsedKnots {
+            routes,
+            links: originals,
+            knots,
+        }
+    }
+
+    /// Puts back the knots removed by `collapse_knots`, and the links of the pins that were
+    /// connected directly in their place.
+    pub fn insert_knots(&mut self, collapsed: CollapsedKnots) {
+        for (i, n) in collapsed.knots {
+            let i = i.min(self.nodes.len());
+            self.nodes.insert(i, n);
+        }
+        for (pin, links) in collapsed.links {
+            if let Some(pin) = self.resolve_link(&pin) {
+                self.nodes[pin.node].pins[pin.pin].set_linked_to(links);
+            }
+        }
+    }
+
+    fn is_knot_link(&self, link: &Link) -> bool {
+        self.node(&link.node_name).is_some_and(Node::is_knot)
+    }
+
+    /// Follows a link to a knot pin out of the other side of the knot, returning the pins of
+    /// other nodes reached, the knot pins they are connected to and the knots passed through.
+    ///
+    /// `path` holds the knots on the way to this one. Routes that split and meet again are all
+    /// followed, and only a knot already on the path is not entered again.
+    fn through_knots(&self, link: &Link, path: &mut Vec<String>) -> Vec<(Link, Link, Vec<String>)> {
+        let knot = match self.node(&link.node_name) {
+            Some(knot) if !path.contains(&knot.name) => knot,
+            _ => return Vec::new(),
+        };
+        path.push(knot.name.clone());
+        let mut ends = Vec::new();
+        for p in knot.pins.iter().filter(|p| p.id != link.pin_id) {
+            for next in &p.linked_to {
+                if self.is_knot_link(next) {
+                    for (end, last, mut knots) in self.through_knots(next, path) {
+                        knots.insert(0, knot.name.clone());
+                        ends.push((end, last, knots));
+                    }
+                } else {
+                    let last = Link {
+                        node_name: knot.name.clone(),
+                        pin_id: p.id,
+                    };
+                    ends.push((next.clone(), last, vec![knot.name.clone()]));
+                }
+            }
+        }
+        path.pop();
+        ends
+    }
+}
+
+fn push_unique(links: &mut Vec<Link>, link: Link) {
+    if !links.contains(&link) {
+        links.push(link);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{graph::model::Link, read_graph, read_object};
+
+    #[test]
+    fn collapse_knots() {
+        let code = include_str!("../../k2node_codes/knot.bp");
+        let mut graph = read_graph(code).unwrap();
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges().edges.len(), 5);
+
+        let collapsed = graph.collapse_knots();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(
+            graph.to_objects(),
+            read_object(include_str!("../../k2node_codes/two_nodes.bp")).unwrap()
+        );
+        assert_eq!(
+            collapsed
+                .routes
+                .iter()
+                .map(|r| r.knots.join(","))
+                .collect::<Vec<_>>(),
+            vec![
+                "K2Node_Knot_2",
+                "K2Node_Knot_1,K2Node_Knot_0",
+                "K2Node_Knot_2",
+                "K2Node_Knot_0,K2Node_Knot_1"
+            ]
+        );
+        assert_eq!(collapsed.knots.len(), 3);
+
+        graph.insert_knots(collapsed);
+        assert_eq!(graph.to_objects(), read_object(code).unwrap());
+    }
+
+    #[test]
+    fn collapse_fan_out_knots() {
+        let code = include_str!("../../k2node_codes/knot_fan_out.bp");
+        let mut graph = read_graph(code).unwrap();
+        let original = graph.to_objects();
+        let collapsed = graph.collapse_knots();
+        let routes = |collapsed: &super::CollapsedKnots| {
+            collapsed
+                .routes
+                .iter()
+                .map(|r| (r.knots.join(","), r.to.node_name.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            routes(&collapsed),
+            vec![
+                (
+                    "K2Node_Knot_0,K2Node_Knot_1".to_owned(),
+                    "K2Node_CallFunction_4678".to_owned()
+                ),
+                (
+                    "K2Node_Knot_0,K2Node_Knot_2".to_owned(),
+                    "K2Node_CallFunction_4679".to_owned()
+                ),
+                (
+                    "K2Node_Knot_1,K2Node_Knot_0".to_owned(),
+                    "K2Node_InputAxisEvent_160".to_owned()
+                ),
+                (
+                    "K2Node_Knot_2,K2Node_Knot_0".to_owned(),
+                    "K2Node_InputAxisEvent_160".to_owned()
+                )
+            ]
+        );
+        assert_eq!(graph.nodes[0].pins[2].linked_to.len(), 2);
+        graph.insert_knots(collapsed);
+        assert_eq!(graph.to_objects(), original);
+
+        // Let the second route meet the first one again at Knot_1.
+        let mut graph = read_graph(code).unwrap();
+        let node = |name| graph.node_index(name).unwrap();
+        let (knot_1, knot_2, pitch) = (
+            node("K2Node_Knot_1"),
+            node("K2Node_Knot_2"),
+            node("K2Node_CallFunction_4679"),
+        );
+        let link = |node: usize, pin: usize| Link {
+            node_name: graph.nodes[node].name.clone(),
+            pin_id: graph.nodes[node].pins[pin].id,
+        };
+        let (to_knot_1, from_knot_2) = (link(knot_1, 0), link(knot_2, 1));
+        let mut inputs = graph.nodes[knot_1].pins[0].linked_to.clone();
+        inputs.push(from_knot_2);
+        graph.nodes[knot_1].pins[0].set_linked_to(inputs);
+        graph.nodes[knot_2].pins[1].set_linked_to(vec![to_knot_1]);
+        graph.nodes[pitch].pins[3].set_linked_to(Vec::new());
+        let original = graph.to_objects();
+        let collapsed = graph.collapse_knots();
+        assert_eq!(
+            routes(&collapsed),
+            vec![
+                (
+                    "K2Node_Knot_0,K2Node_Knot_1".to_owned(),
+                    "K2Node_CallFunction_4678".to_owned()
+                ),
+                (
+                    "K2Node_Knot_0,K2Node_Knot_2,K2Node_Knot_1".to_owned(),
+                    "K2Node_CallFunction_4678".to_owned()
+                ),
+                (
+                    "K2Node_Knot_1,K2Node_Knot_0".to_owned(),
+                    "K2Node_InputAxisEvent_160".to_owned()
+                ),
+                (
+                    "K2Node_Knot_1,K2Node_Knot_2,K2Node_Knot_0".to_owned(),
+                    "K2Node_InputAxisEvent_160".to_owned()
+                )
+            ]
+        );
+        graph.insert_knots(collapsed);
+        assert_eq!(graph.to_objects(), original);
+    }
+
+    #[test]
+    fn collapse_dangling_knots() {
+        let code = include_str!("../../k2node_codes/test2.bp");
+        let mut graph = read_graph(code).unwrap();
+        let collapsed = graph.collapse_knots();
+        assert!(collapsed.routes.is_empty());
+        assert_eq!(collapsed.knots.len(), 3);
+        assert!(graph.nodes.iter().all(|n| !n.is_knot()));
+
+        graph.insert_knots(collapsed);
+        assert_eq!(graph.to_objects(), read_object(code).unwrap());
+    }
+
+    #[test]
+    fn collapse_knots_without_source() {
+        let code = include_str!("../../k2node_codes/knot.bp");
+        let start = code.find("Begin Object Class=/Script/BlueprintGraph.K2Node_InputAxisEvent");
+        let end = code.find("Begin Object Class=/Script/BlueprintGraph.K2Node_Knot");
+        let code = format!("{}{}", &code[..start.unwrap()], &code[end.unwrap()..]);
+        let mut graph = read_graph(&code).unwrap();
+        let original = graph.to_objects();
+
+        let collapsed = graph.collapse_knots();
+        let execute = &graph.node("K2Node_CallFunction_4678").unwrap().pins[0];
+        assert_eq!(execute.linked_to[0].node_name, "K2Node_InputAxisEvent_160");
+        assert_eq!(collapsed.links.len(), 2);
+        graph.insert_knots(collapsed);
+        assert_eq!(graph.to_objects(), original);
+
+        // A knot that leads nowhere drops the link, which is restored as well.
+        let mut graph = read_graph(&code).unwrap();
+        let knot = graph.node_index("K2Node_Knot_2").unwrap();
+        graph.nodes[knot].pins[0].set_linked_to(Vec::new());
+        let original = graph.to_objects();
+        let collapsed = graph.collapse_knots();
+        let execute = &graph.node("K2Node_CallFunction_4678").unwrap().pins[0];
+        assert!(execute.linked_to.is_empty());
+        graph.insert_knots(collapsed);
+        assert_eq!(graph.to_objects(), original);
+    }
+}
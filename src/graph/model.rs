@@ -0,0 +1,319 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::pin_type::PinType;
+use crate::parser::ast::{
+    find_prop, CustomProp, CustomPropValue, LinkedTo, Object, ObjectElement, Objects, Prop,
+    PropValue,
+};
+
+/// The direction of a pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PinDirection {
+    Input,
+    Output,
+}
+
+/// A connection from a pin to a pin of another node, as written in `LinkedTo`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Link {
+    pub node_name: String,
+    pub pin_id: Uuid,
+}
+
+/// A structure that represents a pin of a node.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Pin {
+    pub id: Uuid,
+    pub name: String,
+    pub direction: PinDirection,
+    pub pin_type: PinType,
+    pub default_value: Option<String>,
+    pub linked_to: Vec<Link>,
+    /// All properties of the pin as they were parsed.
+    pub props: Vec<Prop>,
+}
+
+/// The position of a node on the graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// A structure that represents a node of a graph.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Node {
+    /// The class path, e.g. `/Script/BlueprintGraph.K2Node_CallFunction`.
+    pub class: String,
+    pub name: String,
+    pub guid: Option<Uuid>,
+    pub position: Position,
+    pub comment: Option<String>,
+    pub pins: Vec<Pin>,
+    /// The object the node was built from.
+    pub object: Object,
+}
+
+/// A graph of nodes built from the objects of a Blueprint graph copy.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BlueprintGraph {
+    pub nodes: Vec<Node>,
+}
+
+impl Pin {
+    /// Builds a pin from the properties of a `CustomProperties Pin` line.
+    pub fn from_props(props: Vec<Prop>) -> Result<Pin> {
+        let id = find_prop(&props, "PinId")
+            .and_then(PropValue::as_uuid)
+            .ok_or_else(|| anyhow!("pin has no PinId"))?;
+        let direction = match find_prop(&props, "Direction").and_then(PropValue::as_str) {
+            Some("EGPD_Output") => PinDirection::Output,
+            _ => PinDirection::Input,
+        };
+        let linked_to = match find_prop(&props, "LinkedTo") {
+            Some(PropValue::LinkedToList(v)) => v
+                .iter()
+                .map(|l| Link {
+                    node_name: l.name.clone(),
+                    pin_id: l.uuid,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(Pin {
+            id,
+            name: prop_string(&props, "PinName").unwrap_or_default(),
+            direction,
+            pin_type: PinType::from_props(&props),
+            default_value: prop_string(&props, "DefaultValue"),
+            linked_to,
+            props,
+        })
+    }
+
+    /// Returns the value of a property of the pin.
+    pub fn prop(&self, key: &str) -> Option<&PropValue> {
+        find_prop(&self.props, key)
+    }
+
+    /// Replaces the connections of the pin, keeping the `LinkedTo` property in sync. The
+    /// property is left out when there are no connections, as the editor does.
+    pub fn set_linked_to(&mut self, links: Vec<Link>) {
+        let value = PropValue::LinkedToList(
+            links
+                .iter()
+                .map(|l| LinkedTo {
+                    name: l.node_name.clone(),
+                    uuid: l.pin_id,
+                })
+                .collect(),
+        );
+        match self.props.iter().position(|p| p.key == "LinkedTo") {
+            Some(i) if links.is_empty() => {
+                self.props.remove(i);
+            }
+            Some(i) => self.props[i].value = value,
+            None if links.is_empty() => (),
+            None => {
+                let i = self
+                    .props
+                    .iter()
+                    .position(|p| p.key == "PersistentGuid")
+                    .unwrap_or(self.props.len());
+                self.props.insert(
+                    i,
+                    Prop {
+                        key: "LinkedTo".to_owned(),
+                        value,
+                    },
+                );
+            }
+        }
+        self.linked_to = links;
+    }
+}
+
+impl Node {
+    /// Builds a node from a parsed object.
+    pub fn from_object(object: Object) -> Result<Node> {
+        let name = object
+            .header_prop("Name")
+            .and_then(PropValue::as_str)
+            .ok_or_else(|| anyhow!("object has no Name"))?
+            .to_owned();
+        let class = object
+            .header_prop("Class")
+            .and_then(PropValue::as_str)
+            .unwrap_or_default()
+            .to_owned();
+        let pins = object
+            .custom_props()
+            .filter_map(|c| match &c.value {
+                CustomPropValue::Pin(props) => Some(
+                    Pin::from_props(props.clone()).map_err(|e| anyhow!("{} in node {}", e, name)),
+                ),
+                _ => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Node {
+            class,
+            guid: object.prop("NodeGuid").and_then(PropValue::as_uuid),
+            position: Position {
+                x: object
+                    .prop("NodePosX")
+                    .and_then(PropValue::as_i64)
+                    .unwrap_or(0),
+                y: object
+                    .prop("NodePosY")
+                    .and_then(PropValue::as_i64)
+                    .unwrap_or(0),
+            },
+            comment: object
+                .prop("NodeComment")
+                .and_then(PropValue::as_str)
+                .map(str::to_owned),
+            name,
+            pins,
+            object,
+        })
+    }
+
+    /// Returns the class name without the package, e.g. `K2Node_CallFunction`.
+    pub fn class_name(&self) -> &str {
+        self.class.rsplit('.').next().unwrap_or_default()
+    }
+
+    /// Returns the value of a Key / Value property of the node.
+    pub fn prop(&self, key: &str) -> Option<&PropValue> {
+        self.object.prop(key)
+    }
+
+    /// Finds a pin by its id.
+    pub fn pin(&self, id: Uuid) -> Option<&Pin> {
+        self.pins.iter().find(|p| p.id == id)
+    }
+
+    /// Finds a pin by its name.
+    pub fn pin_by_name(&self, name: &str) -> Option<&Pin> {
+        self.pins.iter().find(|p| p.name == name)
+    }
+
+    /// Returns the object of the node with the properties of its pins as they are now.
+    pub fn to_object(&self) -> Object {
+        let mut object = self.object.clone();
+        let pins = object.elements.iter_mut().filter_map(|e| match e {
+            ObjectElement::CustomProp(CustomProp {
+                value: CustomPropValue::Pin(props),
+                ..
+            }) => Some(props),
+            _ => None,
+        });
+        for (props, pin) in pins.zip(&self.pins) {
+            *props = pin.props.clone();
+        }
+        object
+    }
+}
+
+impl BlueprintGraph {
+    /// Builds a graph in which every top-level object is a node.
+    pub fn from_objects(objects: Objects) -> Result<BlueprintGraph> {
+        Ok(BlueprintGraph {
+            nodes: objects
+                .0
+                .into_iter()
+                .map(Node::from_object)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Finds a node by its name.
+    pub fn node(&self, name: &str) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+
+    /// Returns the index of the node with the given name.
+    pub fn node_index(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    /// Returns the objects of the nodes, e.g. to write the graph back with `write_object`.
+    pub fn to_objects(&self) -> Objects {
+        Objects(self.nodes.iter().map(Node::to_object).collect())
+    }
+}
+
+fn prop_string(props: &[Prop], key: &str) -> Option<String> {
+    find_prop(props, key)
+        .and_then(PropValue::as_str)
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::pin_type::PinCategory;
+    use crate::read_object;
+
+    #[test]
+    fn build_graph() {
+        let objects = read_object(include_str!("../../k2node_codes/two_nodes.bp")).unwrap();
+        let graph = BlueprintGraph::from_objects(objects).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+
+        let call = &graph.nodes[0];
+        assert_eq!(call.class, "/Script/BlueprintGraph.K2Node_CallFunction");
+        assert_eq!(call.class_name(), "K2Node_CallFunction");
+        assert_eq!(call.name, "K2Node_CallFunction_4678");
+        assert_eq!(
+            call.guid,
+            Some(Uuid::parse_str("72D31250462697EE90B27CBFC0957A6D").unwrap())
+        );
+        assert_eq!(call.position, Position { x: 1088, y: -448 });
+        assert_eq!(call.comment, None);
+        assert_eq!(call.pins.len(), 4);
+
+        let execute = call.pin_by_name("execute").unwrap();
+        assert_eq!(execute.direction, PinDirection::Input);
+        assert_eq!(execute.pin_type.category, PinCategory::Exec);
+        assert_eq!(
+            execute.linked_to,
+            vec![Link {
+                node_name: "K2Node_InputAxisEvent_160".to_owned(),
+                pin_id: Uuid::parse_str("FCB984164512320C9D4784B5D1D93263").unwrap()
+            }]
+        );
+        let then = call.pin_by_name("then").unwrap();
+        assert_eq!(then.direction, PinDirection::Output);
+        assert!(then.linked_to.is_empty());
+        assert_eq!(
+            call.pin_by_name("Val").unwrap().default_value,
+            Some("0.0".to_owned())
+        );
+
+        let event = graph.node("K2Node_InputAxisEvent_160").unwrap();
+        assert_eq!(
+            event.prop("InputAxisName"),
+            Some(&PropValue::String("Turn".to_owned()))
+        );
+        assert_eq!(graph.node_index("K2Node_InputAxisEvent_160"), Some(1));
+    }
+
+    #[test]
+    fn build_comment_node() {
+        let objects = read_object(include_str!("../../k2node_codes/comment.bp")).unwrap();
+        let graph = BlueprintGraph::from_objects(objects).unwrap();
+        let comment = &graph.nodes[0];
+        assert_eq!(comment.class_name(), "EdGraphNode_Comment");
+        assert_eq!(comment.comment, Some("Mouse input".to_owned()));
+        assert!(comment.pins.is_empty());
+    }
+
+    #[test]
+    fn build_graph_without_name() {
+        let objects = read_object("Begin Object\nEnd Object\n").unwrap();
+        assert!(BlueprintGraph::from_objects(objects).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Serialize, Serializer};
+use strum_macros::{EnumString, IntoStaticStr};
+
+use crate::parser::ast::{find_prop, ObjectPath, Prop, PropValue};
+
+/// The category of a pin type, as written in `PinType.PinCategory`. It is serialized with the
+/// same spelling.
+#[derive(Debug, Clone, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum PinCategory {
+    Exec,
+    #[strum(serialize = "bool")]
+    Boolean,
+    Byte,
+    Int,
+    Int64,
+    Float,
+    Double,
+    /// Floating point numbers of UE5, in which `PinSubCategory` is `float` or `double`.
+    Real,
+    Name,
+    String,
+    Text,
+    Struct,
+    Enum,
+    Object,
+    Class,
+    Interface,
+    #[strum(serialize = "softobject")]
+    SoftObject,
+    #[strum(serialize = "softclass")]
+    SoftClass,
+    Delegate,
+    #[strum(serialize = "mcdelegate")]
+    MulticastDelegate,
+    #[strum(serialize = "fieldpath")]
+    FieldPath,
+    Wildcard,
+    /// A category this crate does not know.
+    #[strum(disabled)]
+    Other(String),
+}
+
+/// The type of the values of a map pin, written in `PinType.PinValueType`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PinValueType {
+    pub category: PinCategory,
+    pub sub_category: Option<String>,
+    pub sub_category_object: Option<ObjectPath>,
+    pub is_const: bool,
+    pub is_weak_pointer: bool,
+    pub is_uobject_wrapper: bool,
+}
+
+/// The container of a pin type, written in `PinType.ContainerType`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PinContainer {
+    None,
+    Array,
+    Set,
+    /// A map whose keys are of the pin type and whose values are of the given type.
+    Map(PinValueType),
+}
+
+/// The type of a pin, assembled from the `PinType.*` properties of a pin.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PinType {
+    pub category: PinCategory,
+    /// `PinSubCategory`, e.g. `self` or the precision of a `real` pin.
+    pub sub_category: Option<String>,
+    /// The struct, enum or class of the pin, e.g. `/Script/CoreUObject.Vector`.
+    pub sub_category_object: Option<ObjectPath>,
+    pub container: PinContainer,
+    pub is_reference: bool,
+    pub is_const: bool,
+    pub is_weak_pointer: bool,
+    pub is_uobject_wrapper: bool,
+}
+
+impl PinCategory {
+    /// Returns the category as written in `PinType.PinCategory`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            PinCategory::Other(s) => s,
+            c => c.into(),
+        }
+    }
+}
+
+impl Serialize for PinCategory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl PinValueType {
+    /// Builds a value type from the properties of `PinType.PinValueType`.
+    pub fn from_props(props: &[Prop]) -> PinValueType {
+        PinValueType {
+            category: category(props, "TerminalCategory"),
+            sub_category: sub_category(props, "TerminalSubCategory"),
+            sub_category_object: sub_category_object(props, "TerminalSubCategoryObject"),
+            is_const: flag(props, "bTerminalIsConst"),
+            is_weak_pointer: flag(props, "bTerminalIsWeakPointer"),
+            is_uobject_wrapper: flag(props, "bTerminalIsUObjectWrapper"),
+        }
+    }
+}
+
+impl PinType {
+    /// Builds a pin type from the properties of a `CustomProperties Pin` line.
+    pub fn from_props(props: &[Prop]) -> PinType {
+        let container = match find_prop(props, "PinType.ContainerType").and_then(PropValue::as_str)
+        {
+            Some("Array") => PinContainer::Array,
+            Some("Set") => PinContainer::Set,
+            Some("Map") => PinContainer::Map(match find_prop(props, "PinType.PinValueType") {
+                Some(PropValue::PropList(v)) => PinValueType::from_props(v),
+                _ => PinValueType::from_props(&[]),
+            }),
+            _ => PinContainer::None,
+        };
+        PinType {
+            category: category(props, "PinType.PinCategory"),
+            sub_category: sub_category(props, "PinType.PinSubCategory"),
+            sub_category_object: sub_category_object(props, "PinType.PinSubCategoryObject"),
+            container,
+            is_reference: flag(props, "PinType.bIsReference"),
+            is_const: flag(props, "PinType.bIsConst"),
+            is_weak_pointer: flag(props, "PinType.bIsWeakPointer"),
+            is_uobject_wrapper: flag(props, "PinType.bIsUObjectWrapper"),
+        }
+    }
+
+    /// Whether this is the type of an execution pin.
+    pub fn is_exec(&self) -> bool {
+        self.category == PinCategory::Exec
+    }
+}
+
+fn category(props: &[Prop], key: &str) -> PinCategory {
+    let name = find_prop(props, key)
+        .and_then(PropValue::as_str)
+        .unwrap_or_default();
+    name.parse()
+        .unwrap_or_else(|_| PinCategory::Other(name.to_owned()))
+}
+
+fn sub_category(props: &[Prop], key: &str) -> Option<String> {
+    find_prop(props, key)
+        .and_then(PropValue::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+}
+
+fn sub_category_object(props: &[Prop], key: &str) -> Option<ObjectPath> {
+    match find_prop(props, key) {
+        Some(PropValue::ObjectReference { path, .. }) => Some(path.clone()),
+        _ => None,
+    }
+}
+
+fn flag(props: &[Prop], key: &str) -> bool {
+    find_prop(props, key)
+        .and_then(PropValue::as_bool)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::CustomPropValue;
+    use crate::parser::prop::prop_custom_props;
+
+    fn pin_type(line: &str) -> PinType {
+        match prop_custom_props(line).unwrap().1.value {
+            CustomPropValue::Pin(props) => PinType::from_props(&props),
+            v => panic!("not a pin: {:?}", v),
+        }
+    }
+
+    #[test]
+    fn decode_pin_type() {
+        let t = pin_type(
+            "CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,PinName=\"execute\",PinType.PinCategory=\"exec\",PinType.PinSubCategory=\"\",PinType.PinSubCategoryObject=None,PinType.PinValueType=(),PinType.ContainerType=None,PinType.bIsReference=False,PinType.bIsConst=False,PinType.bIsWeakPointer=False,PinType.bIsUObjectWrapper=False,)",
+        );
+        assert!(t.is_exec());
+        assert_eq!(t.category.as_str(), "exec");
+        assert_eq!(t.sub_category, None);
+        assert_eq!(t.sub_category_object, None);
+        assert_eq!(t.container, PinContainer::None);
+        assert!(!t.is_reference);
+
+        let t = pin_type(
+            "CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,PinName=\"Location\",PinType.PinCategory=\"struct\",PinType.PinSubCategoryObject=ScriptStruct'\"/Script/CoreUObject.Vector\"',PinType.ContainerType=Array,PinType.bIsReference=True,PinType.bIsConst=True,)",
+        );
+        assert_eq!(t.category, PinCategory::Struct);
+        assert_eq!(
+            t.sub_category_object,
+            Some(ObjectPath::from("/Script/CoreUObject.Vector"))
+        );
+        assert_eq!(t.container, PinContainer::Array);
+        assert!(t.is_reference && t.is_const);
+
+        let t = pin_type(
+            "CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,PinName=\"Scores\",PinType.PinCategory=\"name\",PinType.PinValueType=(TerminalCategory=\"real\",TerminalSubCategory=\"double\"),PinType.ContainerType=Map,)",
+        );
+        assert_eq!(t.category, PinCategory::Name);
+        match t.container {
+            PinContainer::Map(v) => {
+                assert_eq!(v.category, PinCategory::Real);
+                assert_eq!(v.sub_category, Some("double".to_owned()));
+            }
+            c => panic!("not a map: {:?}", c),
+        }
+
+        let t = pin_type(
+            "CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,PinType.PinCategory=\"mcdelegate\",PinType.ContainerType=Set,)",
+        );
+        assert_eq!(t.category, PinCategory::MulticastDelegate);
+        assert_eq!(t.category.as_str(), "mcdelegate");
+        assert_eq!(t.container, PinContainer::Set);
+
+        let t = pin_type("CustomProperties Pin (PinType.PinCategory=\"optional\",)");
+        assert_eq!(t.category, PinCategory::Other("optional".to_owned()));
+        assert_eq!(t.category.as_str(), "optional");
+
+        let t = pin_type("CustomProperties Pin (PinType.PinCategory=\"other\",)");
+        assert_eq!(t.category, PinCategory::Other("other".to_owned()));
+    }
+
+    #[test]
+    fn serialize_pin_category() {
+        let json = |c: PinCategory| serde_json::to_string(&c).unwrap();
+        assert_eq!(json(PinCategory::Boolean), r#""bool""#);
+        assert_eq!(json(PinCategory::MulticastDelegate), r#""mcdelegate""#);
+        assert_eq!(json(PinCategory::Int64), r#""int64""#);
+        assert_eq!(
+            json(PinCategory::Other("optional".to_owned())),
+            r#""optional""#
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use super::edge::{Edges, PinRef};
+use super::exec::{ExecGraph, Flow};
+use super::model::{BlueprintGraph, Pin, PinDirection};
+use super::pin_type::PinCategory;
+use crate::parser::ast::{find_prop, PropValue};
+
+const INDENT: &str = "    ";
+
+/// Renders the exec flow of a graph as pseudocode, walking from each event node.
+struct Renderer<'a> {
+    graph: &'a BlueprintGraph,
+    edges: Edges,
+    exec: ExecGraph,
+    out: String,
+}
+
+impl BlueprintGraph {
+    /// Renders the graph as readable pseudocode, one block per event node.
+    ///
+    /// Pure nodes are inlined as expressions into the inputs they feed, unconnected inputs show
+    /// their default values, and the outputs of executed nodes are referred to by pin name.
+    pub fn to_pseudocode(&self) -> String {
+        let mut r = Renderer {
+            graph: self,
+            edges: self.edges(),
+            exec: self.exec_graph(),
+            out: String::new(),
+        };
+        for (i, &entry) in r.exec.entries.clone().iter().enumerate() {
+            if i > 0 {
+                r.out.push('\n');
+            }
+            r.event(entry);
+        }
+        r.out
+    }
+}
+
+impl<'a> Renderer<'a> {
+    fn event(&mut self, node: usize) {
+        let n = &self.graph.nodes[node];
+        let name = match n.class_name() {
+            "K2Node_CustomEvent" => n.prop("CustomFunctionName").and_then(PropValue::as_str),
+            "K2Node_InputAxisEvent" => n.prop("InputAxisName").and_then(PropValue::as_str),
+            _ => member_name(n.prop("EventReference")),
+        }
+        .unwrap_or(&n.name);
+        let params: Vec<String> = n
+            .pins
+            .iter()
+            .filter(|p| {
+                p.direction == PinDirection::Output
+                    && !matches!(
+                        p.pin_type.category,
+                        PinCategory::Exec | PinCategory::Delegate
+                    )
+            })
+            .map(|p| identifier(&p.name))
+            .collect();
+        self.out
+            .push_str(&format!("event {}({}) {{\n", name, params.join(", ")));
+        self.flow(node, 1, &mut Vec::new());
+        self.out.push_str("}\n");
+    }
+
+    /// Writes the statements run after the node `node` has run.
+    fn flow(&mut self, node: usize, depth: usize, path: &mut Vec<usize>) {
+        let flow = match self.exec.node(node) {
+            Some(e) => e.flow.clone(),
+            None => return,
+        };
+        path.push(node);
+        match flow {
+            Flow::Next { then } => self.next(then, depth, path),
+            Flow::Branch {
+                condition,
+                if_true,
+                if_false,
+            } => {
+                let condition =
+                    condition.map_or("false".to_owned(), |c| self.input(c, &mut Vec::new()));
+                self.line(depth, &format!("if ({}) {{", condition));
+                self.next(if_true, depth + 1, path);
+                if if_false.is_some() {
+                    self.line(depth, "} else {");
+                    self.next(if_false, depth + 1, path);
+                }
+                self.line(depth, "}");
+            }
+            Flow::Sequence(targets) => {
+                for target in targets {
+                    self.next(target, depth, path);
+                }
+            }
+            Flow::Outputs(outputs) => {
+                for (name, target) in outputs.into_iter().filter(|(_, t)| t.is_some()) {
+                    self.line(depth, &format!("{}: {{", identifier(&name)));
+                    self.next(target, depth + 1, path);
+                    self.line(depth, "}");
+                }
+            }
+        }
+        path.pop();
+    }
+
+    /// Writes the node the exec pin `target` belongs to and everything run after it.
+    fn next(&mut self, target: Option<PinRef>, depth: usize, path: &mut Vec<usize>) {
+        let node = match target {
+            Some(t) => t.node,
+            None => return,
+        };
+        let n = &self.graph.nodes[node];
+        if path.contains(&node) {
+            self.line(depth, &format!("goto {};", n.name));
+            return;
+        }
+        if n.is_knot() {
+            self.flow(node, depth, path);
+            return;
+        }
+        match self.exec.node(node).map(|e| &e.flow) {
+            Some(Flow::Branch { .. }) | Some(Flow::Sequence(_)) => self.flow(node, depth, path),
+            Some(Flow::Outputs(_)) => {
+                let statement = self.statement(node);
+                self.line(depth, &format!("{} {{", statement));
+                self.flow(node, depth + 1, path);
+                self.line(depth, "}");
+            }
+            _ => {
+                let statement = self.statement(node);
+                self.line(depth, &format!("{};", statement));
+                self.flow(node, depth, path);
+            }
+        }
+    }
+
+    fn statement(&self, node: usize) -> String {
+        let n = &self.graph.nodes[node];
+        match n.class_name() {
+            "K2Node_VariableSet" => {
+                let name = n.variable_name().unwrap_or_default();
+                let value = n
+                    .pins
+                    .iter()
+                    .position(|p| p.name == name && p.direction == PinDirection::Input)
+                    .map_or("None".to_owned(), |pin| {
+                        self.input(PinRef { node, pin }, &mut Vec::new())
+                    });
+                format!("{} = {}", name, value)
+            }
+            _ => self.call(node, &mut Vec::new()),
+        }
+    }
+
+    /// Returns the expression for the node `node`, like a function call. `path` holds the pure
+    /// nodes whose expressions contain this one.
+    fn call(&self, node: usize, path: &mut Vec<usize>) -> String {
+        let n = &self.graph.nodes[node];
+        let mut input = |name: &str| {
+            n.pins
+                .iter()
+                .position(|p| p.name == name && p.direction == PinDirection::Input)
+                .map(|pin| self.input(PinRef { node, pin }, path))
+        };
+        match n.class_name() {
+            "K2Node_VariableGet" => {
+                let name = n.variable_name().unwrap_or(&n.name);
+                match self.target(node, path) {
+                    Some(target) => format!("{}.{}", target, name),
+                    None => name.to_owned(),
+                }
+            }
+            "K2Node_Knot" => input("InputPin").unwrap_or_default(),
+            "K2Node_Self" => "self".to_owned(),
+            "K2Node_DynamicCast" => {
+                let class = match n.prop("TargetType") {
+                    Some(PropValue::ObjectReference { path, .. }) => {
+                        path.asset().unwrap_or_else(|| path.as_str()).to_owned()
+                    }
+                    _ => String::new(),
+                };
+                format!("Cast<{}>({})", class, input("Object").unwrap_or_default())
+            }
+            class => {
+                let name = member_name(n.prop("FunctionReference"))
+                    .unwrap_or_else(|| class.trim_start_matches("K2Node_"));
+                let args: Vec<String> = n
+                    .pins
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| {
+                        p.direction == PinDirection::Input
+                            && p.name != "self"
+                            && !p.pin_type.is_exec()
+                            && !(p.prop("bHidden").and_then(PropValue::as_bool) == Some(true)
+                                && p.linked_to.is_empty())
+                    })
+                    .map(|(pin, _)| self.input(PinRef { node, pin }, path))
+                    .collect();
+                let call = format!("{}({})", name, args.join(", "));
+                match self.target(node, path) {
+                    Some(target) => format!("{}.{}", target, call),
+                    None => call,
+                }
+            }
+        }
+    }
+
+    /// Returns the expression of the connected `self` pin of a node, if any.
+    fn target(&self, node: usize, path: &mut Vec<usize>) -> Option<String> {
+        let pin = self.graph.nodes[node].pins.iter().position(|p| {
+            p.name == "self" && p.direction == PinDirection::Input && !p.linked_to.is_empty()
+        })?;
+        Some(self.input(PinRef { node, pin }, path))
+    }
+
+    /// Returns the expression for the value of the input pin `pin`.
+    fn input(&self, pin: PinRef, path: &mut Vec<usize>) -> String {
+        let output = match self.edges.incoming(pin).next() {
+            Some(output) => output,
+            None => return default_value(self.graph.pin(pin)),
+        };
+        let n = &self.graph.nodes[output.node];
+        let name = &self.graph.pin(output).name;
+        // Pure nodes never form cycles in a valid graph, but do not loop on broken input.
+        if !n.is_pure() || path.contains(&output.node) {
+            return identifier(name);
+        }
+        path.push(output.node);
+        let call = self.call(output.node, path);
+        path.pop();
+        let outputs = n
+            .pins
+            .iter()
+            .filter(|p| p.direction == PinDirection::Output)
+            .count();
+        if outputs > 1 && name != "ReturnValue" && !n.is_knot() {
+            format!("{}.{}", call, identifier(name))
+        } else {
+            call
+        }
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        self.out.push_str(&INDENT.repeat(depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+}
+
+fn member_name(reference: Option<&PropValue>) -> Option<&str> {
+    match reference {
+        Some(PropValue::PropList(v)) => find_prop(v, "MemberName").and_then(PropValue::as_str),
+        _ => None,
+    }
+}
+
+/// Returns the default value of an unconnected input pin as a literal.
+fn default_value(pin: &Pin) -> String {
+    let value = [
+        "DefaultValue",
+        "DefaultTextValue",
+        "AutogeneratedDefaultValue",
+    ]
+    .iter()
+    .find_map(|key| pin.prop(key).and_then(PropValue::as_str));
+    match (&pin.pin_type.category, value) {
+        (PinCategory::String | PinCategory::Name | PinCategory::Text, v) => {
+            format!("{:?}", v.unwrap_or_default())
+        }
+        (_, Some(v)) if !v.is_empty() => v.to_owned(),
+        _ => "None".to_owned(),
+    }
+}
+
+/// Turns a pin name such as `AsBP Slider` into an identifier.
+fn identifier(name: &str) -> String {
+    name.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{graph::model::Link, read_graph};
+
+    #[test]
+    fn render_pseudocode() {
+        let graph = read_graph(include_str!("../../k2node_codes/test2.bp")).unwrap();
+        assert_eq!(
+            graph.to_pseudocode(),
+            r#"event ReceiveTick(DeltaSeconds) {
+    if (PhysicsHandleActive) {
+        UpdatePhysicsHandle();
+    }
+    CheckTrace();
+    if (IsSlider) {
+        Cast<BP_Slider_C>(HitActor) {
+            then: {
+                AsBPSlider.SetColor(LessEqual_FloatFloat(GetDistanceTo(HitActor), 1024));
+            }
+        }
+    }
+    CheckTrace();
+    if (CanScroll) {
+        Cast<BP_Slider_C>(HitActor) {
+            then: {
+                AsBPSlider.AllowScrolling(LessEqual_FloatFloat(GetDistanceTo(HitActor), 1024));
+            }
+        }
+    }
+}
+"#
+        );
+
+        let graph = read_graph(include_str!("../../k2node_codes/knot.bp")).unwrap();
+        assert_eq!(
+            graph.to_pseudocode(),
+            "event Turn(AxisValue) {\n    AddControllerYawInput(AxisValue);\n}\n"
+        );
+    }
+
+    #[test]
+    fn render_pure_cycle() {
+        // Detach the knots of the AxisValue connection and loop them onto themselves.
+        let mut graph = read_graph(include_str!("../../k2node_codes/knot.bp")).unwrap();
+        let node = |name| graph.node_index(name).unwrap();
+        let (knot_0, knot_1, event) = (
+            node("K2Node_Knot_0"),
+            node("K2Node_Knot_1"),
+            node("K2Node_InputAxisEvent_160"),
+        );
+        let link = |node: usize, pin: usize| Link {
+            node_name: graph.nodes[node].name.clone(),
+            pin_id: graph.nodes[node].pins[pin].id,
+        };
+        let (input, output) = (link(knot_0, 0), link(knot_1, 1));
+        let mut outputs = graph.nodes[knot_1].pins[1].linked_to.clone();
+        outputs.push(input);
+        graph.nodes[knot_1].pins[1].set_linked_to(outputs);
+        graph.nodes[knot_0].pins[0].set_linked_to(vec![output]);
+        let axis_value = graph.nodes[event]
+            .pins
+            .iter()
+            .position(|p| p.name == "AxisValue")
+            .unwrap();
+        graph.nodes[event].pins[axis_value].set_linked_to(Vec::new());
+        assert_eq!(
+            graph.to_pseudocode(),
+            "event Turn(AxisValue) {\n    AddControllerYawInput(OutputPin);\n}\n"
+        );
+    }
+}
@@ -0,0 +1,643 @@
+//! The versioned JSON representation of Objects.
+//!
+//! The types in this module define the JSON format produced by `to_json`. They are kept apart from
+//! the AST so that the format only changes on purpose: any breaking change to it must bump
+//! `FORMAT_VERSION` and be reflected in `schema()`.
+
+use std::convert::{TryFrom, TryInto};
+
+use anyhow::{anyhow, bail, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::parser::ast;
+
+/// The version of the JSON format. Bumped on every breaking change.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The root of the JSON document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub objects: Vec<Object>,
+}
+
+/// An object block. `type` is the word after `Begin`, such as `Object` or `Actor`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Object {
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub header: Vec<Prop>,
+    pub elements: Vec<Element>,
+}
+
+/// An element of an object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Element {
+    Prop { key: String, value: Value },
+    CustomProp { domain: String, value: CustomValue },
+    Object { object: Object },
+}
+
+/// A Key / Value property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prop {
+    pub key: String,
+    pub value: Value,
+}
+
+/// A property value, tagged with its type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Value {
+    String {
+        value: String,
+    },
+    Integer {
+        value: i64,
+    },
+    /// `text` is the number as written in the source. It may be omitted when reading.
+    Double {
+        value: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    Boolean {
+        value: bool,
+    },
+    None,
+    Enum {
+        value: String,
+    },
+    /// A GUID written as 32 uppercase hex digits, as Unreal does.
+    Guid {
+        value: String,
+    },
+    Nsloctext {
+        namespace: String,
+        key: String,
+        source: String,
+    },
+    Loctext {
+        key: String,
+        source: String,
+    },
+    Invtext {
+        source: String,
+    },
+    Loctable {
+        table: String,
+        key: String,
+    },
+    /// A `LOCGEN_*` text. `generator` is the full name, such as `LOCGEN_NUMBER`.
+    Locgen {
+        generator: String,
+        args: Vec<Value>,
+    },
+    ObjectReference {
+        class: String,
+        path: String,
+    },
+    LinkedTo {
+        links: Vec<Link>,
+    },
+    Struct {
+        props: Vec<Prop>,
+    },
+    Array {
+        values: Vec<Value>,
+    },
+    StaticArray {
+        elements: Vec<StaticArrayElement>,
+    },
+    Other {
+        value: String,
+    },
+}
+
+/// An element of a static array.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaticArrayElement {
+    pub index: usize,
+    pub value: Value,
+}
+
+/// A connection to the pin `pin` of the node `node`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    pub node: String,
+    pub pin: String,
+}
+
+/// The payload of a CustomProperties line, tagged with its type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CustomValue {
+    Pin { props: Vec<Prop> },
+    UserDefinedPin { props: Vec<Prop> },
+    Other { value: Value },
+}
+
+fn guid_to_string(uuid: &Uuid) -> String {
+    format!("{:X}", uuid.to_simple_ref())
+}
+
+fn guid_from_str(s: &str) -> Result<Uuid> {
+    Uuid::parse_str(s).map_err(|e| anyhow!("invalid GUID {:?}: {}", s, e))
+}
+
+fn props_to_json(props: &[ast::Prop]) -> Vec<Prop> {
+    props.iter().map(Prop::from).collect()
+}
+
+fn props_from_json(props: Vec<Prop>) -> Result<Vec<ast::Prop>> {
+    props.into_iter().map(TryInto::try_into).collect()
+}
+
+impl From<&ast::Objects> for Document {
+    fn from(objects: &ast::Objects) -> Self {
+        Document {
+            version: FORMAT_VERSION,
+            objects: objects.0.iter().map(Object::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<Document> for ast::Objects {
+    type Error = Error;
+
+    fn try_from(doc: Document) -> Result<Self> {
+        if doc.version != FORMAT_VERSION {
+            bail!(
+                "unsupported JSON format version {} (expected {})",
+                doc.version,
+                FORMAT_VERSION
+            );
+        }
+        let objects = doc.objects.into_iter().map(TryInto::try_into);
+        Ok(ast::Objects(objects.collect::<Result<_>>()?))
+    }
+}
+
+impl From<&ast::Object> for Object {
+    fn from(o: &ast::Object) -> Self {
+        Object {
+            object_type: o.header.object_type.clone(),
+            header: props_to_json(&o.header.header_props),
+            elements: o.elements.iter().map(Element::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<Object> for ast::Object {
+    type Error = Error;
+
+    fn try_from(o: Object) -> Result<Self> {
+        Ok(ast::Object {
+            header: ast::ObjectHeader {
+                object_type: o.object_type,
+                header_props: props_from_json(o.header)?,
+            },
+            elements: o
+                .elements
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl From<&ast::ObjectElement> for Element {
+    fn from(e: &ast::ObjectElement) -> Self {
+        match e {
+            ast::ObjectElement::Prop(p) => Element::Prop {
+                key: p.key.clone(),
+                value: Value::from(&p.value),
+            },
+            ast::ObjectElement::CustomProp(c) => Element::CustomProp {
+                domain: c.domain.clone(),
+                value: CustomValue::from(&c.value),
+            },
+            ast::ObjectElement::Object(o) => Element::Object {
+                object: Object::from(o),
+            },
+        }
+    }
+}
+
+impl TryFrom<Element> for ast::ObjectElement {
+    type Error = Error;
+
+    fn try_from(e: Element) -> Result<Self> {
+        Ok(match e {
+            Element::Prop { key, value } => ast::ObjectElement::Prop(ast::Prop {
+                key,
+                value: value.try_into()?,
+            }),
+            Element::CustomProp { domain, value } => {
+                ast::ObjectElement::CustomProp(ast::CustomProp {
+                    domain,
+                    value: value.try_into()?,
+                })
+            }
+            Element::Object { object } => ast::ObjectElement::Object(object.try_into()?),
+        })
+    }
+}
+
+impl From<&ast::Prop> for Prop {
+    fn from(p: &ast::Prop) -> Self {
+        Prop {
+            key: p.key.clone(),
+            value: Value::from(&p.value),
+        }
+    }
+}
+
+impl TryFrom<Prop> for ast::Prop {
+    type Error = Error;
+
+    fn try_from(p: Prop) -> Result<Self> {
+        Ok(ast::Prop {
+            key: p.key,
+            value: p.value.try_into()?,
+        })
+    }
+}
+
+impl From<&ast::PropValue> for Value {
+    fn from(v: &ast::PropValue) -> Self {
+        use ast::PropValue as P;
+        match v {
+            P::String(v) => Value::String { value: v.clone() },
+            P::Integer(v) => Value::Integer { value: *v },
+            P::Double(v) => Value::Double {
+                value: v.value(),
+                text: Some(v.text().to_owned()),
+            },
+            P::Boolean(v) => Value::Boolean { value: *v },
+            P::None => Value::None,
+            P::Enum(v) => Value::Enum { value: v.clone() },
+            P::Uuid(v) => Value::Guid {
+                value: guid_to_string(v),
+            },
+            P::Text(ast::Text::NsLoc {
+                namespace,
+                key,
+                source,
+            }) => Value::Nsloctext {
+                namespace: namespace.clone(),
+                key: key.clone(),
+                source: source.clone(),
+            },
+            P::Text(ast::Text::Loc { key, source }) => Value::Loctext {
+                key: key.clone(),
+                source: source.clone(),
+            },
+            P::Text(ast::Text::Invariant(source)) => Value::Invtext {
+                source: source.clone(),
+            },
+            P::Text(ast::Text::LocTable { table, key }) => Value::Loctable {
+                table: table.clone(),
+                key: key.clone(),
+            },
+            P::Text(ast::Text::Generated { generator, args }) => Value::Locgen {
+                generator: generator.clone(),
+                args: args.iter().map(Value::from).collect(),
+            },
+            P::ObjectReference { class, path } => Value::ObjectReference {
+                class: class.clone(),
+                path: path.as_str().to_owned(),
+            },
+            P::LinkedToList(v) => Value::LinkedTo {
+                links: v
+                    .iter()
+                    .map(|l| Link {
+                        node: l.name.clone(),
+                        pin: guid_to_string(&l.uuid),
+                    })
+                    .collect(),
+            },
+            P::PropList(v) => Value::Struct {
+                props: props_to_json(v),
+            },
+            P::Array(v) => Value::Array {
+                values: v.iter().map(Value::from).collect(),
+            },
+            P::StaticArray(v) => Value::StaticArray {
+                elements: v
+                    .iter()
+                    .map(|e| StaticArrayElement {
+                        index: e.index,
+                        value: Value::from(&e.value),
+                    })
+                    .collect(),
+            },
+            P::Other(v) => Value::Other { value: v.clone() },
+        }
+    }
+}
+
+impl TryFrom<Value> for ast::PropValue {
+    type Error = Error;
+
+    fn try_from(v: Value) -> Result<Self> {
+        use ast::PropValue as P;
+        Ok(match v {
+            Value::String { value } => P::String(value),
+            Value::Integer { value } => P::Integer(value),
+            Value::Double { value, text } => P::Double(
+                text.and_then(|t| ast::Double::from_text(&t))
+                    .filter(|d| d.value() == value)
+                    .or_else(|| ast::Double::new(value))
+                    .ok_or_else(|| anyhow!("invalid double {}", value))?,
+            ),
+            Value::Boolean { value } => P::Boolean(value),
+            Value::None => P::None,
+            Value::Enum { value } => P::Enum(value),
+            Value::Guid { value } => P::Uuid(guid_from_str(&value)?),
+            Value::Nsloctext {
+                namespace,
+                key,
+                source,
+            } => P::Text(ast::Text::NsLoc {
+                namespace,
+                key,
+                source,
+            }),
+            Value::Loctext { key, source } => P::Text(ast::Text::Loc { key, source }),
+            Value::Invtext { source } => P::Text(ast::Text::Invariant(source)),
+            Value::Loctable { table, key } => P::Text(ast::Text::LocTable { table, key }),
+            Value::Locgen { generator, args } => P::Text(ast::Text::Generated {
+                generator,
+                args: args
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_>>()?,
+            }),
+            Value::ObjectReference { class, path } => P::ObjectReference {
+                class,
+                path: ast::ObjectPath(path),
+            },
+            Value::LinkedTo { links } => P::LinkedToList(
+                links
+                    .into_iter()
+                    .map(|l| {
+                        Ok(ast::LinkedTo {
+                            name: l.node,
+                            uuid: guid_from_str(&l.pin)?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Struct { props } => P::PropList(props_from_json(props)?),
+            Value::Array { values } => P::array(
+                values
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_>>()?,
+            ),
+            Value::StaticArray { elements } => P::StaticArray(
+                elements
+                    .into_iter()
+                    .map(|e| {
+                        Ok(ast::StaticArrayElement {
+                            index: e.index,
+                            value: e.value.try_into()?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Other { value } => P::Other(value),
+        })
+    }
+}
+
+impl From<&ast::CustomPropValue> for CustomValue {
+    fn from(v: &ast::CustomPropValue) -> Self {
+        match v {
+            ast::CustomPropValue::Pin(v) => CustomValue::Pin {
+                props: props_to_json(v),
+            },
+            ast::CustomPropValue::UserDefinedPin(v) => CustomValue::UserDefinedPin {
+                props: props_to_json(v),
+            },
+            ast::CustomPropValue::Other(v) => CustomValue::Other {
+                value: Value::from(v),
+            },
+        }
+    }
+}
+
+impl TryFrom<CustomValue> for ast::CustomPropValue {
+    type Error = Error;
+
+    fn try_from(v: CustomValue) -> Result<Self> {
+        Ok(match v {
+            CustomValue::Pin { props } => ast::CustomPropValue::Pin(props_from_json(props)?),
+            CustomValue::UserDefinedPin { props } => {
+                ast::CustomPropValue::UserDefinedPin(props_from_json(props)?)
+            }
+            CustomValue::Other { value } => ast::CustomPropValue::Other(value.try_into()?),
+        })
+    }
+}
+
+/// Converts the Objects into the JSON format.
+pub fn to_string(objects: &ast::Objects, pretty: bool) -> Result<String> {
+    let doc = Document::from(objects);
+    if pretty {
+        Ok(serde_json::to_string_pretty(&doc)?)
+    } else {
+        Ok(serde_json::to_string(&doc)?)
+    }
+}
+
+/// Restores the Objects from the JSON format.
+pub fn from_str(json: &str) -> Result<ast::Objects> {
+    let doc: Document = serde_json::from_str(json)?;
+    doc.try_into()
+}
+
+/// Builds a tagged variant of the schema with the given fields.
+fn variant(tag_key: &str, tag: &str, fields: serde_json::Value) -> serde_json::Value {
+    let mut properties = fields.as_object().cloned().unwrap_or_default();
+    let mut required = vec![json!(tag_key)];
+    required.extend(properties.keys().map(|k| json!(k)));
+    properties.insert(tag_key.to_owned(), json!({ "const": tag }));
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Returns the JSON Schema of the JSON format.
+pub fn schema() -> serde_json::Value {
+    let string = json!({ "type": "string" });
+    let guid = json!({ "type": "string", "pattern": "^[0-9A-F]{32}$" });
+    let props = json!({ "type": "array", "items": { "$ref": "#/$defs/prop" } });
+    let value = |tag: &str, fields| variant("type", tag, fields);
+    let mut double = value("double", json!({ "value": { "type": "number" } }));
+    double["properties"]["text"] = string.clone();
+    let index = json!({ "type": "integer", "minimum": 0 });
+    let value_ref = json!({ "$ref": "#/$defs/value" });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Unreal Engine objects",
+        "type": "object",
+        "properties": {
+            "version": { "const": FORMAT_VERSION },
+            "objects": { "type": "array", "items": { "$ref": "#/$defs/object" } },
+        },
+        "required": ["version", "objects"],
+        "additionalProperties": false,
+        "$defs": {
+            "object": {
+                "type": "object",
+                "properties": {
+                    "type": string,
+                    "header": props,
+                    "elements": { "type": "array", "items": { "$ref": "#/$defs/element" } },
+                },
+                "required": ["type", "header", "elements"],
+                "additionalProperties": false,
+            },
+            "element": {
+                "oneOf": [
+                    variant("kind", "prop", json!({ "key": string, "value": value_ref })),
+                    variant("kind", "custom_prop", json!({ "domain": string, "value": { "$ref": "#/$defs/custom_value" } })),
+                    variant("kind", "object", json!({ "object": { "$ref": "#/$defs/object" } })),
+                ],
+            },
+            "prop": {
+                "type": "object",
+                "properties": { "key": string, "value": value_ref },
+                "required": ["key", "value"],
+                "additionalProperties": false,
+            },
+            "value": {
+                "oneOf": [
+                    value("string", json!({ "value": string })),
+                    value("integer", json!({ "value": { "type": "integer" } })),
+                    double,
+                    value("boolean", json!({ "value": { "type": "boolean" } })),
+                    value("none", json!({})),
+                    value("enum", json!({ "value": string })),
+                    value("guid", json!({ "value": guid })),
+                    value("nsloctext", json!({ "namespace": string, "key": string, "source": string })),
+                    value("loctext", json!({ "key": string, "source": string })),
+                    value("invtext", json!({ "source": string })),
+                    value("loctable", json!({ "table": string, "key": string })),
+                    value("locgen", json!({ "generator": string, "args": { "type": "array", "items": value_ref } })),
+                    value("object_reference", json!({ "class": string, "path": string })),
+                    value("linked_to", json!({ "links": { "type": "array", "items": { "$ref": "#/$defs/link" } } })),
+                    value("struct", json!({ "props": props })),
+                    value("array", json!({ "values": { "type": "array", "items": value_ref } })),
+                    value("static_array", json!({ "elements": { "type": "array", "items": { "$ref": "#/$defs/static_array_element" } } })),
+                    value("other", json!({ "value": string })),
+                ],
+            },
+            "static_array_element": {
+                "type": "object",
+                "properties": { "index": index, "value": value_ref },
+                "required": ["index", "value"],
+                "additionalProperties": false,
+            },
+            "link": {
+                "type": "object",
+                "properties": { "node": string, "pin": guid },
+                "required": ["node", "pin"],
+                "additionalProperties": false,
+            },
+            "custom_value": {
+                "oneOf": [
+                    value("pin", json!({ "props": props })),
+                    value("user_defined_pin", json!({ "props": props })),
+                    value("other", json!({ "value": { "$ref": "#/$defs/value" } })),
+                ],
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_object;
+
+    #[test]
+    fn json_format() {
+        let code = r#"Begin Object Class=/Script/BlueprintGraph.K2Node_VariableGet Name="A"
+   NodePosX=-16
+   Scale=1e3
+   CustomProperties Pin (PinId=F6D0DA4A4AA531533341018A20422309,LinkedTo=(K2Node_B 570BAD4542CBB0285413EEAB4F6DBDDA,),)
+End Object
+"#;
+        let json: serde_json::Value =
+            serde_json::from_str(&to_string(&read_object(code).unwrap(), false).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "version": 1,
+                "objects": [{
+                    "type": "Object",
+                    "header": [
+                        { "key": "Class", "value": { "type": "string", "value": "/Script/BlueprintGraph.K2Node_VariableGet" } },
+                        { "key": "Name", "value": { "type": "string", "value": "A" } },
+                    ],
+                    "elements": [
+                        { "kind": "prop", "key": "NodePosX", "value": { "type": "integer", "value": -16 } },
+                        { "kind": "prop", "key": "Scale", "value": { "type": "double", "value": 1000.0, "text": "1e3" } },
+                        { "kind": "custom_prop", "domain": "Pin", "value": { "type": "pin", "props": [
+                            { "key": "PinId", "value": { "type": "guid", "value": "F6D0DA4A4AA531533341018A20422309" } },
+                            { "key": "LinkedTo", "value": { "type": "linked_to", "links": [
+                                { "node": "K2Node_B", "pin": "570BAD4542CBB0285413EEAB4F6DBDDA" },
+                            ] } },
+                        ] } },
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn json_version() {
+        assert!(from_str(r#"{"version":1,"objects":[]}"#).is_ok());
+        let v: ast::PropValue = serde_json::from_str::<Value>(r#"{"type":"double","value":2.5}"#)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(v, ast::PropValue::Double(ast::Double::new(2.5).unwrap()));
+        let e = from_str(r#"{"version":2,"objects":[]}"#).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "unsupported JSON format version 2 (expected 1)"
+        );
+    }
+
+    #[test]
+    fn empty_array_round_trip() {
+        let v: ast::PropValue = serde_json::from_str::<Value>(r#"{"type":"array","values":[]}"#)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(v, ast::PropValue::PropList(Vec::new()));
+        let text = crate::writer::write_prop_value(&v);
+        assert_eq!(crate::parser::prop::prop_value(&text), Ok(("", v)));
+    }
+
+    #[test]
+    fn schema_file_is_up_to_date() {
+        let file: serde_json::Value =
+            serde_json::from_str(include_str!("../schema/objects.schema.json")).unwrap();
+        assert_eq!(
+            file,
+            schema(),
+            "run `k2node-to-json --schema -o schema/objects.schema.json` to update"
+        );
+    }
+}
@@ -1,29 +1,85 @@
+pub mod error;
+pub mod graph;
+pub mod json;
 pub mod parser;
+pub mod writer;
 
-use anyhow::{anyhow, Result};
-use parser::ast::Objects;
+use anyhow::Result;
+use error::ParseError;
+use graph::model::BlueprintGraph;
+use parser::{ast::Objects, cst::Document, diagnostic::diagnose, recovery::Recovered};
 
 /// Parses the serialized text representing the Object and stores the information in the Object
 /// structure.
-pub fn read_object(objects_code: &str) -> Result<Objects> {
+///
+/// On failure, the error points at the line and column where parsing stopped.
+pub fn read_object(objects_code: &str) -> Result<Objects, ParseError> {
     match parser::object::objects(objects_code) {
-        Ok(obj) => {
-            if obj.0.len() != 0 {
-                Err(anyhow!("Text is left after parsing is complete"))
-            } else {
-                Ok(obj.1)
-            }
-        }
-        Err(err) => Err(anyhow!("parse error: {}", err)),
+        Ok(("", obj)) => Ok(obj),
+        Ok((remain, _)) => Err(diagnose(objects_code, remain)),
+        Err(_) => Err(diagnose(objects_code, objects_code)),
+    }
+}
+
+/// Parses the serialized text, skipping the objects that cannot be parsed. The errors of the
+/// skipped objects are returned alongside the objects that were parsed.
+pub fn read_object_tolerant(objects_code: &str) -> Recovered {
+    parser::recovery::objects_tolerant(objects_code)
+}
+
+/// Parses the serialized text into a Document that keeps the original formatting.
+pub fn read_document(objects_code: &str) -> Result<Document, ParseError> {
+    match parser::lossless::document(objects_code) {
+        Ok(("", doc)) => Ok(doc),
+        Ok((remain, _)) => Err(diagnose(objects_code, remain)),
+        Err(_) => Err(diagnose(objects_code, objects_code)),
     }
 }
 
+/// Writes the Document back into text. Lines that were not modified are kept byte for byte.
+pub fn write_document(doc: &Document) -> String {
+    writer::write_document(doc)
+}
+
+/// Writes the Objects back into the serialized text that can be pasted into the Unreal editor.
+pub fn write_object(objects: &Objects) -> String {
+    writer::write_objects(objects)
+}
+
+/// Parses the serialized text of a Blueprint graph copy and builds a typed graph from it.
+pub fn read_graph(objects_code: &str) -> Result<BlueprintGraph> {
+    BlueprintGraph::from_objects(read_object(objects_code)?)
+}
+
 /// Parses the serialized text representing the Object and returns it as JSON.
+///
+/// The format is described by `json::schema()` and versioned by `json::FORMAT_VERSION`.
 pub fn to_json(objects_code: &str, pretty: bool) -> Result<String> {
-    let obj = read_object(objects_code)?;
-    if pretty {
-        Ok(serde_json::to_string_pretty(&obj)?)
-    } else {
-        Ok(serde_json::to_string(&obj)?)
+    json::to_string(&read_object(objects_code)?, pretty)
+}
+
+/// Restores the Objects from the JSON produced by `to_json`.
+pub fn from_json(json: &str) -> Result<Objects> {
+    json::from_str(json)
+}
+
+/// Parses the serialized text of a Blueprint graph copy and renders its exec flow as pseudocode.
+pub fn to_pseudocode(objects_code: &str) -> Result<String> {
+    Ok(read_graph(objects_code)?.to_pseudocode())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trip() {
+        let code = include_str!("../k2node_codes/test2.bp");
+        let objects = read_object(code).unwrap();
+        for pretty in [false, true] {
+            let json = to_json(code, pretty).unwrap();
+            assert_eq!(from_json(&json).unwrap(), objects);
+        }
+        assert!(from_json(r#"{"version":1,"objects":[{"header":[]}]}"#).is_err());
     }
 }
@@ -2,3 +2,8 @@ pub mod literal;
 pub mod object;
 pub mod prop;
 pub mod ast;
+pub mod cst;
+pub mod lossless;
+pub mod diagnostic;
+pub mod recovery;
+pub mod schema;
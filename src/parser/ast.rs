@@ -1,30 +1,141 @@
-use serde::Serialize;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 /// A structure that holds node pins and other connection destination information.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LinkedTo {
     pub name: String,
     pub uuid: Uuid,
 }
 
+/// A floating point number that remembers how it was written, such as `330.000000` or `1e-3`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Double {
+    value: f64,
+    text: String,
+}
+
 /// An enumerated type that holds basic properties.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PropValue {
     String(String),
     Integer(i64),
-    Double(f64),
+    Double(Double),
     Boolean(bool),
+    /// The null value `None`, such as an empty object reference.
+    None,
+    /// An enum value such as `EGPD_Output`.
+    Enum(String),
+    /// A GUID, for properties known to hold one such as `NodeGuid` or `PinId`.
     Uuid(Uuid),
-    NslocText(String, String, String),
-    ObjectReference(String, String),
+    Text(Text),
+    /// A reference such as `Class'"/Script/Engine.Actor"'`. The class may itself be a path, as in
+    /// `/Script/CoreUObject.Class'/Script/Engine.Actor'`.
+    ObjectReference { class: String, path: ObjectPath },
     LinkedToList(Vec<LinkedTo>),
     PropList(Vec<Prop>),
+    /// A list of values without keys, such as `("A","B")` or `((X=1,Y=2),(X=3,Y=4))`. Never
+    /// empty, as `()` is read as a `PropList`; build it with `PropValue::array`.
+    Array(Vec<PropValue>),
+    /// The elements of a static array, written as `Key(0)=Value` lines. Each line is read as a
+    /// property with one element, and `group_static_arrays` merges them.
+    StaticArray(Vec<StaticArrayElement>),
+    /// A bare token whose type is unknown, kept as written.
     Other(String),
 }
 
+/// A path to an object, such as `/Game/Maps/Foo.Foo:PersistentLevel.Actor_1`: a package, an
+/// asset in the package and a chain of sub-objects in the asset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ObjectPath(pub String);
+
+impl ObjectPath {
+    /// Returns the path as written.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the package, such as `/Game/Maps/Foo`.
+    pub fn package(&self) -> &str {
+        self.0.split(['.', ':']).next().unwrap_or_default()
+    }
+
+    /// Returns the asset in the package, such as `Foo`. A path in which the package is followed
+    /// by `:` names no asset.
+    pub fn asset(&self) -> Option<&str> {
+        match self.0.find(['.', ':']) {
+            Some(i) if self.0[i..].starts_with('.') => self.0[i + 1..]
+                .split(['.', ':'])
+                .next()
+                .filter(|a| !a.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Returns the chain of sub-objects in the asset, such as `["PersistentLevel", "Actor_1"]`.
+    pub fn sub_objects(&self) -> Vec<&str> {
+        match self.0.find(['.', ':']) {
+            Some(i) => {
+                let parts = self.0[i + 1..].split(['.', ':']);
+                let skip = usize::from(self.0[i..].starts_with('.'));
+                parts.skip(skip).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl From<&str> for ObjectPath {
+    fn from(path: &str) -> Self {
+        ObjectPath(path.to_owned())
+    }
+}
+
+/// A localizable text (FText) literal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Text {
+    /// `NSLOCTEXT("Namespace", "Key", "Source")`
+    NsLoc {
+        namespace: String,
+        key: String,
+        source: String,
+    },
+    /// `LOCTEXT("Key", "Source")`
+    Loc { key: String, source: String },
+    /// `INVTEXT("Source")`, a text that is not localized.
+    Invariant(String),
+    /// `LOCTABLE("TableId", "Key")`, a text taken from a string table.
+    LocTable { table: String, key: String },
+    /// A generated text such as `LOCGEN_NUMBER(10, "")` or `LOCGEN_TOUPPER(...)`.
+    Generated {
+        generator: String,
+        args: Vec<PropValue>,
+    },
+}
+
+impl Text {
+    /// Returns the source string of the text, if the text carries one.
+    pub fn source_string(&self) -> Option<&str> {
+        match self {
+            Text::NsLoc { source, .. } | Text::Loc { source, .. } | Text::Invariant(source) => {
+                Some(source)
+            }
+            Text::LocTable { .. } | Text::Generated { .. } => None,
+        }
+    }
+}
+
+/// An element of a static array property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaticArrayElement {
+    pub index: usize,
+    pub value: PropValue,
+}
+
 /// An enumerated type that indicates the internal elements of an object.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ObjectElement {
     Prop(Prop),
     CustomProp(CustomProp),
@@ -33,46 +144,366 @@ pub enum ObjectElement {
 
 
 /// A structure that represents the basic Key / Value properties.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Prop {
     pub key: String,
     pub value: PropValue,
 }
 
 /// A structure that indicates custom properties.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CustomPropValue {
     Pin(Vec<Prop>),
+    UserDefinedPin(Vec<Prop>),
+    /// The payload of any other domain. Payloads that are not a single value are kept as
+    /// `PropValue::Other` with the raw text.
+    Other(PropValue),
 }
 
 /// A structure of custom properties held by an object.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CustomProp {
     pub domain: String,
     pub value: CustomPropValue,
 }
 
 /// A structure that represents an object header.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ObjectHeader {
     pub object_type: String,
     pub header_props: Vec<Prop>,
 }
 
 /// A strucutre that represents an object.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Object {
     pub header: ObjectHeader,
     pub elements: Vec<ObjectElement>,
 }
 
 /// A strucutre that represents an object end.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ObjectEnd {
     pub object_type: String,
 }
 
 
 /// A structure that represents the Vec of an object.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Objects(pub Vec<Object>);
+
+impl Double {
+    /// Creates a number written with six decimal places as Unreal writes floats, or in the
+    /// shortest form that reads back exactly when six places would round it. Returns `None` for
+    /// NaN and infinities, which have no literal.
+    pub fn new(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let text = format!("{:.6}", value);
+        let text = match text.parse::<f64>() {
+            Ok(v) if v == value => text,
+            _ => format!("{:?}", value),
+        };
+        Some(Double { value, text })
+    }
+
+    /// Creates a number from its source text. Returns `None` if the text is not a number.
+    pub fn from_text(text: &str) -> Option<Self> {
+        Some(Double {
+            value: text.parse().ok()?,
+            text: text.to_owned(),
+        })
+    }
+
+    /// Returns the value of the number.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the text the number is written as.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl PropValue {
+    /// Creates an `Array` of the values. No values make an empty `PropList`, which is how the
+    /// `()` written for an empty list is read back.
+    pub fn array(values: Vec<PropValue>) -> PropValue {
+        if values.is_empty() {
+            PropValue::PropList(Vec::new())
+        } else {
+            PropValue::Array(values)
+        }
+    }
+
+    /// Returns the value as a string slice if it is a string, an enum value or a bare token.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropValue::String(v) | PropValue::Enum(v) | PropValue::Other(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an integer if it is one.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            PropValue::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a floating point number if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            PropValue::Double(v) => Some(v.value()),
+            PropValue::Integer(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a boolean if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropValue::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an uuid if it is one.
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        match self {
+            PropValue::Uuid(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the value of the first property with the given key.
+pub fn find_prop<'a>(props: &'a [Prop], key: &str) -> Option<&'a PropValue> {
+    props.iter().find(|p| p.key == key).map(|p| &p.value)
+}
+
+fn collect_texts<'a>(key: &'a str, v: &'a PropValue, out: &mut Vec<(&'a str, &'a Text)>) {
+    match v {
+        PropValue::Text(t) => {
+            out.push((key, t));
+            if let Text::Generated { args, .. } = t {
+                for a in args {
+                    collect_texts(key, a, out);
+                }
+            }
+        }
+        PropValue::PropList(props) => {
+            for p in props {
+                collect_texts(&p.key, &p.value, out);
+            }
+        }
+        PropValue::Array(v) => {
+            for a in v {
+                collect_texts(key, a, out);
+            }
+        }
+        PropValue::StaticArray(v) => {
+            for e in v {
+                collect_texts(key, &e.value, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Merges the elements of each static array (`Key(0)=...`, `Key(1)=...`) into one
+/// `PropValue::StaticArray` property, placed where the first element was.
+pub fn group_static_arrays(props: Vec<Prop>) -> Vec<Prop> {
+    group_indexed(props, |p| Some(p))
+}
+
+fn group_indexed<T>(items: Vec<T>, as_prop: impl Fn(&mut T) -> Option<&mut Prop>) -> Vec<T> {
+    let mut r = Vec::with_capacity(items.len());
+    let mut arrays = HashMap::new();
+    for mut item in items {
+        let prop = match as_prop(&mut item) {
+            Some(p) if matches!(p.value, PropValue::StaticArray(_)) => p,
+            _ => {
+                r.push(item);
+                continue;
+            }
+        };
+        let pos = match arrays.get(&prop.key) {
+            Some(&pos) => pos,
+            None => {
+                arrays.insert(prop.key.clone(), r.len());
+                r.push(item);
+                continue;
+            }
+        };
+        if let (PropValue::StaticArray(elements), Some(PropValue::StaticArray(v))) = (
+            &mut prop.value,
+            as_prop(&mut r[pos]).map(|p| &mut p.value),
+        ) {
+            v.append(elements);
+        }
+    }
+    r
+}
+
+impl Object {
+    /// Groups the static array properties of this object and of its nested objects, like
+    /// `group_static_arrays`.
+    pub fn group_static_arrays(&mut self) {
+        let elements = std::mem::take(&mut self.elements);
+        self.elements = group_indexed(elements, |e| match e {
+            ObjectElement::Prop(p) => Some(p),
+            _ => None,
+        });
+        for e in &mut self.elements {
+            if let ObjectElement::Object(o) = e {
+                o.group_static_arrays();
+            }
+        }
+    }
+
+    /// Returns the value of a header property such as `Class` or `Name`.
+    pub fn header_prop(&self, key: &str) -> Option<&PropValue> {
+        find_prop(&self.header.header_props, key)
+    }
+
+    /// Returns the value of a Key / Value property of this object.
+    pub fn prop(&self, key: &str) -> Option<&PropValue> {
+        self.elements.iter().find_map(|e| match e {
+            ObjectElement::Prop(p) if p.key == key => Some(&p.value),
+            _ => None,
+        })
+    }
+
+    /// Lists the texts held by the properties of this object and of its child objects, including
+    /// the ones nested in lists, pins and generated texts, together with the key of the property
+    /// holding each.
+    pub fn texts(&self) -> Vec<(&str, &Text)> {
+        let mut r = Vec::new();
+        for e in &self.elements {
+            match e {
+                ObjectElement::Prop(p) => collect_texts(&p.key, &p.value, &mut r),
+                ObjectElement::CustomProp(c) => match &c.value {
+                    CustomPropValue::Pin(props) | CustomPropValue::UserDefinedPin(props) => {
+                        for p in props {
+                            collect_texts(&p.key, &p.value, &mut r);
+                        }
+                    }
+                    CustomPropValue::Other(v) => collect_texts(&c.domain, v, &mut r),
+                },
+                ObjectElement::Object(o) => r.extend(o.texts()),
+            }
+        }
+        r
+    }
+
+    /// Iterates over the custom properties of this object.
+    pub fn custom_props(&self) -> impl Iterator<Item = &CustomProp> {
+        self.elements.iter().filter_map(|e| match e {
+            ObjectElement::CustomProp(p) => Some(p),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_object, writer::write_objects};
+
+    #[test]
+    fn group_static_array() {
+        let code = r#"Begin Actor Class=/Script/Engine.StaticMeshActor Name="Mesh"
+   OverrideMaterials(0)=None
+   OverrideMaterials(2)=Material'"/Game/M_Red.M_Red"'
+   Mobility=Movable
+   Begin Object Class=/Script/Engine.StaticMeshComponent Name="Component"
+      Offsets(0)=1
+      Offsets(1)=2
+   End Object
+End Actor
+"#;
+        let mut objects = read_object(code).unwrap();
+        let actor = &mut objects.0[0];
+        actor.group_static_arrays();
+        match actor.prop("OverrideMaterials") {
+            Some(PropValue::StaticArray(v)) => {
+                assert_eq!(v.iter().map(|e| e.index).collect::<Vec<_>>(), [0, 2]);
+            }
+            v => panic!("unexpected value: {:?}", v),
+        }
+        assert!(matches!(actor.elements[1], ObjectElement::Prop(ref p) if p.key == "Mobility"));
+        match &actor.elements[2] {
+            ObjectElement::Object(o) => assert!(matches!(
+                &o.elements[..],
+                [ObjectElement::Prop(Prop {
+                    value: PropValue::StaticArray(v),
+                    ..
+                })] if v.len() == 2
+            )),
+            e => panic!("unexpected element: {:?}", e),
+        }
+        assert_eq!(write_objects(&objects), code);
+    }
+
+    #[test]
+    fn list_texts() {
+        let code = r#"Begin Object Class=/Script/BlueprintGraph.K2Node_CallFunction Name="Call"
+   NodeComment=INVTEXT("Note")
+   CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,PinFriendlyName=NSLOCTEXT("K2Node", "Target", "Target"),DefaultTextValue=LOCGEN_TOUPPER(LOCTEXT("Key", "Hi")),)
+   Begin Object Class=/Script/Engine.Foo Name="Child"
+      Begin Object Class=/Script/Engine.Bar Name="GrandChild"
+         Label=LOCTEXT("GrandChild", "Deep")
+      End Object
+      Label=LOCTEXT("Child", "Sub")
+   End Object
+End Object
+"#;
+        let objects = read_object(code).unwrap();
+        let texts = objects.0[0].texts();
+        let keys: Vec<_> = texts.iter().map(|(k, _)| *k).collect();
+        assert_eq!(
+            keys,
+            [
+                "NodeComment",
+                "PinFriendlyName",
+                "DefaultTextValue",
+                "DefaultTextValue",
+                "Label",
+                "Label"
+            ]
+        );
+        let sources: Vec<_> = texts.iter().filter_map(|(_, t)| t.source_string()).collect();
+        assert_eq!(sources, ["Note", "Target", "Hi", "Deep", "Sub"]);
+    }
+
+    #[test]
+    fn object_path_components() {
+        let path = ObjectPath::from("/Game/Maps/Foo.Foo:PersistentLevel.Actor_1");
+        assert_eq!(path.package(), "/Game/Maps/Foo");
+        assert_eq!(path.asset(), Some("Foo"));
+        assert_eq!(path.sub_objects(), ["PersistentLevel", "Actor_1"]);
+
+        let path = ObjectPath::from("/Script/Engine.Default__CameraActor:SceneComponent");
+        assert_eq!(path.package(), "/Script/Engine");
+        assert_eq!(path.asset(), Some("Default__CameraActor"));
+        assert_eq!(path.sub_objects(), ["SceneComponent"]);
+
+        let path = ObjectPath::from("/Game/Map:PersistentLevel");
+        assert_eq!(path.package(), "/Game/Map");
+        assert_eq!(path.asset(), None);
+        assert_eq!(path.sub_objects(), ["PersistentLevel"]);
+
+        let path = ObjectPath::from("/Game/Map:PersistentLevel.Actor_1");
+        assert_eq!(path.asset(), None);
+        assert_eq!(path.sub_objects(), ["PersistentLevel", "Actor_1"]);
+
+        let path = ObjectPath::from("/Game/Blueprints");
+        assert_eq!(path.package(), "/Game/Blueprints");
+        assert_eq!(path.asset(), None);
+        assert!(path.sub_objects().is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+use super::ast::{CustomProp, Object, ObjectElement, ObjectEnd, ObjectHeader, Objects, Prop};
+
+/// Whitespace around the content of a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    /// Whitespace before the content, including indentation and preceding blank lines.
+    pub leading: String,
+    /// Whitespace after the content, including the line ending.
+    pub trailing: String,
+}
+
+/// The text a line was parsed from, and the value it was parsed into.
+#[derive(Debug, Clone, PartialEq)]
+struct Source<T> {
+    text: String,
+    value: T,
+}
+
+/// A line of the document that remembers the text it was parsed from.
+///
+/// As long as `value` is equal to the value that was parsed, the original text is written back
+/// unchanged. Once `value` is edited, only the content of the line is regenerated and the
+/// surrounding whitespace is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line<T> {
+    pub value: T,
+    /// The whitespace around the line, or `None` to let the writer decide.
+    pub trivia: Option<Trivia>,
+    source: Option<Source<T>>,
+}
+
+impl<T: Clone + PartialEq> Line<T> {
+    /// Creates a line that has no source text, such as a newly inserted property.
+    pub fn new(value: T) -> Line<T> {
+        Line {
+            value,
+            trivia: None,
+            source: None,
+        }
+    }
+
+    /// Creates a line parsed from `text`.
+    pub fn parsed(value: T, text: &str, trivia: Trivia) -> Line<T> {
+        Line {
+            source: Some(Source {
+                text: text.to_owned(),
+                value: value.clone(),
+            }),
+            value,
+            trivia: Some(trivia),
+        }
+    }
+
+    /// Returns the original text of the content if the value has not been modified.
+    pub fn source_text(&self) -> Option<&str> {
+        match &self.source {
+            Some(source) if source.value == self.value => Some(&source.text),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the line has no source text or its value has been modified.
+    pub fn is_modified(&self) -> bool {
+        self.source_text().is_none()
+    }
+}
+
+/// A lossless counterpart of `ObjectElement`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstElement {
+    Prop(Line<Prop>),
+    CustomProp(Line<CustomProp>),
+    Object(CstObject),
+}
+
+/// A lossless counterpart of `Object`.
+///
+/// `end` is expected to have the same object type as `begin`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstObject {
+    pub begin: Line<ObjectHeader>,
+    pub elements: Vec<CstElement>,
+    pub end: Line<ObjectEnd>,
+}
+
+/// A document that keeps the formatting of the text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub objects: Vec<CstObject>,
+    /// Whitespace after the last object.
+    pub trailing: String,
+    /// The line ending used for lines that have no trivia.
+    pub line_ending: String,
+}
+
+impl CstElement {
+    /// Converts the element into its AST counterpart.
+    pub fn to_element(&self) -> ObjectElement {
+        match self {
+            CstElement::Prop(l) => ObjectElement::Prop(l.value.clone()),
+            CstElement::CustomProp(l) => ObjectElement::CustomProp(l.value.clone()),
+            CstElement::Object(o) => ObjectElement::Object(o.to_object()),
+        }
+    }
+}
+
+impl From<&ObjectElement> for CstElement {
+    fn from(e: &ObjectElement) -> CstElement {
+        match e {
+            ObjectElement::Prop(p) => CstElement::Prop(Line::new(p.clone())),
+            ObjectElement::CustomProp(c) => CstElement::CustomProp(Line::new(c.clone())),
+            ObjectElement::Object(o) => CstElement::Object(o.into()),
+        }
+    }
+}
+
+impl CstObject {
+    /// Converts the object into its AST counterpart.
+    pub fn to_object(&self) -> Object {
+        Object {
+            header: self.begin.value.clone(),
+            elements: self.elements.iter().map(CstElement::to_element).collect(),
+        }
+    }
+}
+
+impl From<&Object> for CstObject {
+    fn from(o: &Object) -> CstObject {
+        CstObject {
+            begin: Line::new(o.header.clone()),
+            elements: o.elements.iter().map(CstElement::from).collect(),
+            end: Line::new(ObjectEnd {
+                object_type: o.header.object_type.clone(),
+            }),
+        }
+    }
+}
+
+impl Document {
+    /// Converts the document into the AST.
+    pub fn to_objects(&self) -> Objects {
+        Objects(self.objects.iter().map(CstObject::to_object).collect())
+    }
+}
+
+impl From<&Objects> for Document {
+    fn from(objects: &Objects) -> Document {
+        Document {
+            objects: objects.0.iter().map(CstObject::from).collect(),
+            trailing: String::new(),
+            line_ending: "\n".to_owned(),
+        }
+    }
+}
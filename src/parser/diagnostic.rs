@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+
+use nom::{
+    character::complete::multispace0,
+    error::{ContextError, ErrorKind, FromExternalError, ParseError as NomParseError},
+    sequence::preceded,
+    Err, InputLength,
+};
+
+use crate::error::ParseError;
+
+use super::object::{object, object_element};
+
+/// The result type of the parsers.
+pub type IResult<I, O> = nom::IResult<I, O, SyntaxError<I>>;
+
+/// The error of the parsers. It keeps the furthest position that was reached and what was
+/// expected there, as named by the innermost `context` around the parser that failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError<I> {
+    /// The input at the position of the error.
+    pub input: I,
+    /// A description of what was expected, if a `context` names it.
+    pub expected: Option<Cow<'static, str>>,
+    /// The input at the start of that `context`.
+    pub context: Option<I>,
+    /// The name of the innermost object in which the error occurred.
+    pub object: Option<String>,
+}
+
+impl<I: Clone> SyntaxError<I> {
+    /// Creates an error at `input` that expected `expected` there.
+    pub fn expected(input: I, expected: impl Into<Cow<'static, str>>) -> Self {
+        SyntaxError {
+            input: input.clone(),
+            expected: Some(expected.into()),
+            context: Some(input),
+            object: None,
+        }
+    }
+}
+
+impl<I> SyntaxError<I> {
+    /// Attributes the error to the object named `name`, unless it occurred in a nested object.
+    pub fn in_object(mut self, name: Option<&str>) -> Self {
+        if self.object.is_none() {
+            self.object = name.map(str::to_owned);
+        }
+        self
+    }
+}
+
+impl<I: InputLength> NomParseError<I> for SyntaxError<I> {
+    fn from_error_kind(input: I, _: ErrorKind) -> Self {
+        SyntaxError {
+            input,
+            expected: None,
+            context: None,
+            object: None,
+        }
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    /// Keeps the error that got further. At the same position, a named error is preferred, and
+    /// of two named errors the one whose `context` started later, being the more specific.
+    fn or(self, other: Self) -> Self {
+        let rank = |e: &Self| {
+            (
+                usize::MAX - e.input.input_len(),
+                e.context.as_ref().map(|c| usize::MAX - c.input_len()),
+            )
+        };
+        if rank(&other) >= rank(&self) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<I> ContextError<I> for SyntaxError<I> {
+    fn add_context(input: I, ctx: &'static str, mut other: Self) -> Self {
+        if other.expected.is_none() {
+            other.expected = Some(Cow::Borrowed(ctx));
+            other.context = Some(input);
+        }
+        other
+    }
+}
+
+impl<I: InputLength, E> FromExternalError<I, E> for SyntaxError<I> {
+    fn from_external_error(input: I, kind: ErrorKind, _: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+/// Finds out where and why parsing failed. `s` is the slice of `source` from which the objects
+/// could not be parsed.
+pub fn diagnose(source: &str, s: &str) -> ParseError {
+    diagnose_object(source, s, None)
+}
+
+/// Like `diagnose`, for an object nested in the object named `parent`.
+pub fn diagnose_object(source: &str, s: &str, parent: Option<String>) -> ParseError {
+    let mut p = s;
+    let e = loop {
+        p = match preceded(multispace0, object)(p) {
+            Ok((rest, _)) => rest,
+            Err(Err::Error(e)) | Err(Err::Failure(e)) => break e,
+            Err(Err::Incomplete(_)) => break SyntaxError::expected(p, "`Begin <Type>`"),
+        };
+    };
+    report(source, e, parent)
+}
+
+/// Finds out why the element at `s`, a slice of `source`, could not be parsed.
+pub fn diagnose_line(source: &str, s: &str, object: Option<String>) -> ParseError {
+    let e = match object_element(s) {
+        Err(Err::Error(e)) | Err(Err::Failure(e)) => e,
+        _ => SyntaxError::expected(s, "object element"),
+    };
+    report(source, e, object)
+}
+
+fn report(source: &str, e: SyntaxError<&str>, object: Option<String>) -> ParseError {
+    let expected = e.expected.unwrap_or(Cow::Borrowed("object element"));
+    ParseError::new(source, e.input, e.object.or(object), expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::object::objects;
+
+    fn diagnose_code(code: &str) -> ParseError {
+        match objects(code) {
+            Ok((remain, _)) => diagnose(code, remain),
+            Err(_) => diagnose(code, code),
+        }
+    }
+
+    #[test]
+    fn diagnose_bad_pin_value() {
+        let e = diagnose_code(
+            "Begin Object Name=\"A\"\nEnd Object\nBegin Object Name=\"K2Node_Knot_0\"\n   NodePosX=0\n   CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,PinName=@,)\nEnd Object\n",
+        );
+        assert_eq!((e.line, e.column), (5, 73));
+        assert_eq!(e.object, Some("K2Node_Knot_0".to_owned()));
+        assert_eq!(e.expected, "property value");
+        assert!(e.line_text.starts_with("   CustomProperties Pin (PinId="));
+    }
+
+    #[test]
+    fn diagnose_nested_list() {
+        let e = diagnose_code("Begin Object Name=\"A\"\n   X=(A=1,B=(C=2 D=3))\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 18));
+        assert_eq!(e.expected, "`,` or `)`");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   X=(A=1,B C=2)\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 13));
+        assert_eq!(e.expected, "`=` after property key");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   X=(A=1;B=2)\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 10));
+        assert_eq!(e.expected, "`,` or `)`");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   X=((A=1),(B=2 C=3))\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 18));
+        assert_eq!(e.expected, "`,` or `)`");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   X=(1,2,@)\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 11));
+        assert_eq!(e.expected, "list element");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   Offsets(x)=3\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 12));
+        assert_eq!(e.expected, "array index followed by `)`");
+
+        let e = diagnose_code(
+            "Begin Object Name=\"A\"\n   LinkedTo=(K2Node_Knot_0 XYZ,)\nEnd Object\n",
+        );
+        assert_eq!((e.line, e.column), (2, 28));
+        assert_eq!(e.expected, "pin id of the connection (32 hex digits)");
+    }
+
+    #[test]
+    fn diagnose_object_structure() {
+        let e = diagnose_code(
+            "Begin Object Name=\"A\"\n   Begin Object Name=\"B\"\n   End Level\nEnd Object\n",
+        );
+        assert_eq!((e.line, e.column), (3, 4));
+        assert_eq!(e.object, Some("B".to_owned()));
+        assert_eq!(e.expected, "`End Object`");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   X=1\n");
+        assert_eq!((e.line, e.column), (3, 1));
+        assert_eq!(e.expected, "`End Object`");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\nEnd Object  \n");
+        assert_eq!((e.line, e.column), (2, 11));
+        assert_eq!(e.expected, "line ending");
+
+        let e = diagnose_code("");
+        assert_eq!((e.line, e.column), (1, 1));
+        assert_eq!(e.object, None);
+        assert_eq!(e.expected, "`Begin <Type>`");
+    }
+
+    #[test]
+    fn diagnose_header_and_custom_props() {
+        let e = diagnose_code("Begin Object Class=/Script/Engine.Actor Name=@\nEnd Object\n");
+        assert_eq!((e.line, e.column), (1, 46));
+        assert_eq!(e.expected, "property value");
+
+        let e = diagnose_code("Begin Object Name=A Archetype=Actor'/Game/A\nEnd Object\n");
+        assert_eq!((e.line, e.column), (1, 44));
+        assert_eq!(e.expected, "property value");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   CustomProperties (X=1)\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 21));
+        assert_eq!(e.expected, "custom properties domain");
+
+        let e = diagnose_code("Begin Object Name=\"A\"\n   CustomProperties Pin\nEnd Object\n");
+        assert_eq!((e.line, e.column), (2, 24));
+        assert_eq!(e.expected, "custom properties payload");
+    }
+}
@@ -1,22 +1,22 @@
 use nom::{
     branch::{alt, permutation},
-    bytes::complete::{escaped_transform, tag, take_until1, take_while_m_n},
+    bytes::complete::{escaped_transform, tag, take_till, take_till1, take_while, take_while_m_n},
     character::{
-        complete,
-        complete::{alphanumeric1, char, digit1, multispace0, none_of, space1},
+        complete::{char, digit0, digit1, multispace0, none_of, one_of, satisfy, space1},
         is_hex_digit,
     },
-    combinator::{map, map_res, value},
-    error::{Error, ErrorKind},
-    multi::{count, separated_list0},
-    sequence::delimited,
-    Err, IResult,
+    combinator::{map, map_opt, map_res, not, opt, recognize, value},
+    error::{context, ErrorKind, ParseError},
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, terminated, tuple},
+    Err,
 };
 use uuid::Uuid;
 
 use super::{
-    ast::{LinkedTo, Prop},
-    prop::prop_kv,
+    ast::{Double, LinkedTo, ObjectPath, Prop, PropValue, Text},
+    diagnostic::{IResult, SyntaxError},
+    prop::{prop_kv, prop_value},
 };
 
 /// A parser of string literals.
@@ -48,101 +48,209 @@ pub fn uuid_literal(s: &str) -> IResult<&str, Uuid> {
         take_while_m_n(32, 32, |ch| is_hex_digit(ch as u8)),
         |s: &str| Uuid::parse_str(s),
     )(s)?;
-    if r.0.len() != 0 && is_hex_digit(r.0.chars().nth(0).unwrap() as u8) {
-        Err(Err::Error(Error::new(s, ErrorKind::Fail)))
+    if !r.0.is_empty() && is_hex_digit(r.0.chars().next().unwrap() as u8) {
+        Err(Err::Error(SyntaxError::from_error_kind(s, ErrorKind::Fail)))
     } else {
         Ok(r)
     }
 }
 
-/// A parser for floating point numbers (f64).
-pub fn double(s: &str) -> IResult<&str, f64> {
-    map(
-        permutation((complete::i64, char('.'), digit1)),
-        |v: (_, _, &str)| {
-            let mut r: f64 = v.0 as f64;
-            let s = r / r.abs();
-            for (i, c) in v.2.chars().enumerate() {
-                r += (((c as i32 - 48) as f64) / (10.0 as f64).powf((i + 1) as f64)) * s;
-            }
-            r
-        },
+/// A parser for floating point numbers. Either a decimal point or an exponent is required, so
+/// that integers are left to the integer parser.
+pub fn double(s: &str) -> IResult<&str, Double> {
+    fn exponent(s: &str) -> IResult<&str, &str> {
+        recognize(tuple((one_of("eE"), opt(one_of("+-")), digit1)))(s)
+    }
+    let number = alt((
+        recognize(tuple((digit1, char('.'), digit0, opt(exponent)))),
+        recognize(tuple((char('.'), digit1, opt(exponent)))),
+        recognize(pair(digit1, exponent)),
+    ));
+    map_opt(
+        recognize(pair(opt(one_of("+-")), number)),
+        Double::from_text,
     )(s)
 }
 
 /// A parser of boolean.
 pub fn boolean(s: &str) -> IResult<&str, bool> {
-    map_res(alt((tag("True"), tag("False"))), |v| match v {
-        "True" => Ok(true),
-        "False" => Ok(false),
-        _ => Err(Err::Error(Error::new(s, ErrorKind::Fail))),
-    })(s)
+    terminated(
+        alt((value(true, tag("True")), value(false, tag("False")))),
+        not(satisfy(is_identifier_char)),
+    )(s)
+}
+
+/// A parser of identifiers such as `EGPD_Output`, optionally qualified with `::` or `.` such as
+/// `ECollisionChannel::ECC_Visibility`.
+pub fn identifier(s: &str) -> IResult<&str, &str> {
+    fn segment(s: &str) -> IResult<&str, &str> {
+        recognize(pair(
+            satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+            take_while(is_identifier_char),
+        ))(s)
+    }
+    recognize(pair(
+        segment,
+        many0(pair(alt((tag("::"), tag("."))), segment)),
+    ))(s)
+}
+
+/// A parser for the elements of a non-empty list in parentheses, allowing a trailing comma.
+///
+/// Once an element follows a comma the kind of the list is settled, and a failure is final so
+/// that the error points into the list. With `first_decides`, the first element settles it.
+fn list_literal<'a, T>(
+    mut element: impl FnMut(&'a str) -> IResult<&'a str, T>,
+    first_decides: bool,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    move |s: &'a str| {
+        let (mut s, _) = char('(')(s)?;
+        let mut elements = Vec::new();
+        loop {
+            let (rest, e) = match element(s) {
+                Err(Err::Error(e)) if !elements.is_empty() => return Err(Err::Failure(e)),
+                r => r?,
+            };
+            elements.push(e);
+            let (rest, closed) = match context(
+                "`,` or `)`",
+                alt((
+                    value(true, alt((tag(",)"), tag(")")))),
+                    value(false, tuple((multispace0, tag(","), multispace0))),
+                )),
+            )(rest)
+            {
+                Err(Err::Error(e)) if first_decides => return Err(Err::Failure(e)),
+                r => r?,
+            };
+            if closed {
+                return Ok((rest, elements));
+            }
+            s = rest;
+        }
+    }
 }
 
 /// A parser for lists of linked_object_literal.
 pub fn linkedto_list_literal(s: &str) -> IResult<&str, Vec<LinkedTo>> {
     alt((
         map(tag("()"), |_| Vec::new()),
-        delimited(
-            char('('),
-            separated_list0(
-                permutation((multispace0, tag(","), multispace0)),
-                linked_object_literal,
-            ),
-            alt((tag(",)"), tag(")"))),
-        ),
+        list_literal(linked_object_literal, true),
     ))(s)
 }
 
 /// A parser of literals that represents a list of key/value.
 pub fn kv_list_literal(s: &str) -> IResult<&str, Vec<Prop>> {
-    alt((
-        map(tag("()"), |_| Vec::new()),
+    alt((map(tag("()"), |_| Vec::new()), list_literal(prop_kv, true)))(s)
+}
+
+/// A parser of literals that represents a list of values without keys. An empty list is left to
+/// `kv_list_literal`.
+pub fn array_literal(s: &str) -> IResult<&str, Vec<PropValue>> {
+    list_literal(context("list element", prop_value), false)(s)
+}
+
+/// A parser for text literals: `NSLOCTEXT`, `LOCTEXT`, `INVTEXT`, `LOCTABLE` and the `LOCGEN_*`
+/// family of generated texts.
+pub fn text_literal(s: &str) -> IResult<&str, Text> {
+    let sep = || tuple((multispace0, char(','), multispace0));
+    let (rest, (name, args)) = pair(
+        alt((
+            tag("NSLOCTEXT"),
+            tag("LOCTEXT"),
+            tag("INVTEXT"),
+            tag("LOCTABLE"),
+            recognize(pair(tag("LOCGEN_"), identifier)),
+        )),
         delimited(
-            char('('),
-            separated_list0(permutation((multispace0, tag(","), multispace0)), prop_kv),
-            alt((tag(",)"), tag(")"))),
+            pair(char('('), multispace0),
+            separated_list0(sep(), prop_value),
+            tuple((opt(sep()), multispace0, char(')'))),
         ),
-    ))(s)
+    )(s)?;
+    let strings = |n: usize| -> Option<Vec<String>> {
+        if args.len() != n {
+            return None;
+        }
+        args.iter()
+            .map(|a| match a {
+                PropValue::String(v) => Some(v.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    let text = match name {
+        "NSLOCTEXT" => strings(3).map(|mut v| Text::NsLoc {
+            source: v.remove(2),
+            key: v.remove(1),
+            namespace: v.remove(0),
+        }),
+        "LOCTEXT" => strings(2).map(|mut v| Text::Loc {
+            source: v.remove(1),
+            key: v.remove(0),
+        }),
+        "INVTEXT" => strings(1).map(|mut v| Text::Invariant(v.remove(0))),
+        "LOCTABLE" => strings(2).map(|mut v| Text::LocTable {
+            key: v.remove(1),
+            table: v.remove(0),
+        }),
+        _ => Some(Text::Generated {
+            generator: name.to_owned(),
+            args,
+        }),
+    };
+    match text {
+        Some(t) => Ok((rest, t)),
+        None => Err(Err::Error(SyntaxError::from_error_kind(
+            s,
+            ErrorKind::Verify,
+        ))),
+    }
 }
 
-/// A parser for NSLOCTEXT literals.
-pub fn nsloc_text_literal(s: &str) -> IResult<&str, (String, String, String)> {
-    let sp = permutation((multispace0, alt((tag(","), tag(""))), multispace0));
-    map(
-        permutation((
-            tag("NSLOCTEXT("),
-            count(permutation((string_literal, sp)), 3),
-            alt((tag(",)"), tag(")"))),
-        )),
-        |v| {
-            (
-                v.1[0].0.to_string(),
-                v.1[1].0.to_string(),
-                v.1[2].0.to_string(),
-            )
-        },
+/// A parser of the `None` keyword.
+pub fn none_literal(s: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        terminated(tag("None"), not(satisfy(is_identifier_char))),
     )(s)
 }
 
-/// An object reference literal parser.
-pub fn object_literal(s: &str) -> IResult<&str, (String, String)> {
-    alt((
-        map(tag("None"), |_| ("None".to_string(), "None".to_string())),
-        map(
-            permutation((alphanumeric1, tag("'"), string_literal, tag("'"))),
-            |v| (v.0.to_owned(), v.2),
-        ),
+/// Returns whether the character can be part of an identifier.
+pub fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// A parser of unquoted object paths such as `/Script/Engine.Default__CameraActor:SceneComponent`.
+pub fn object_path(s: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        char('/'),
+        take_till(|c: char| c.is_whitespace() || "'\",()".contains(c)),
     ))(s)
 }
 
-/// A parser of literals representing the node's connection destination.
+/// An object reference literal parser, such as `Class'"/Script/Engine.Actor"'` or
+/// `/Script/Engine.CameraActor'/Script/Engine.Default__CameraActor'`.
+pub fn object_literal(s: &str) -> IResult<&str, (String, ObjectPath)> {
+    map(
+        tuple((
+            alt((object_path, identifier)),
+            char('\''),
+            alt((string_literal, map(object_path, str::to_owned))),
+            char('\''),
+        )),
+        |(class, _, path, _)| (class.to_owned(), ObjectPath(path)),
+    )(s)
+}
+
+/// A parser of literals representing the node's connection destination. The node name ends at
+/// the first character that object names cannot contain.
 pub fn linked_object_literal(s: &str) -> IResult<&str, LinkedTo> {
-    map(permutation((take_until1(" "), space1, uuid_literal)), |v| {
-        LinkedTo {
-            name: v.0.to_owned(),
-            uuid: v.2,
-        }
+    let name = take_till1(|c: char| c.is_whitespace() || ",()".contains(c));
+    let uuid = context("pin id of the connection (32 hex digits)", uuid_literal);
+    map(permutation((name, space1, uuid)), |v| LinkedTo {
+        name: v.0.to_owned(),
+        uuid: v.2,
     })(s)
 }
 
@@ -212,20 +320,73 @@ mod tests {
     }
 
     #[test]
-    fn parse_nsloc_text_literal() {
+    fn parse_text_literal() {
         assert_eq!(
-            nsloc_text_literal(
+            text_literal(
                 r#"NSLOCTEXT("UObjectDisplayNames", "Character:CharacterMovement", "Character Movement")"#
             ),
             Ok((
                 "",
-                (
-                    "UObjectDisplayNames".to_string(),
-                    "Character:CharacterMovement".to_string(),
-                    "Character Movement".to_string()
-                )
+                Text::NsLoc {
+                    namespace: "UObjectDisplayNames".to_string(),
+                    key: "Character:CharacterMovement".to_string(),
+                    source: "Character Movement".to_string()
+                }
             ))
         );
+        assert_eq!(
+            text_literal(r#"LOCTEXT("Greeting", "Hello")"#),
+            Ok((
+                "",
+                Text::Loc {
+                    key: "Greeting".to_string(),
+                    source: "Hello".to_string()
+                }
+            ))
+        );
+        assert_eq!(
+            text_literal(r#"INVTEXT("Debug only")"#),
+            Ok(("", Text::Invariant("Debug only".to_string())))
+        );
+        assert_eq!(
+            text_literal(r#"LOCTABLE("/Game/Strings.Strings", "Title")"#),
+            Ok((
+                "",
+                Text::LocTable {
+                    table: "/Game/Strings.Strings".to_string(),
+                    key: "Title".to_string()
+                }
+            ))
+        );
+        assert_eq!(
+            text_literal(r#"LOCGEN_TOUPPER(LOCTEXT("Key", "Hi")), X"#),
+            Ok((
+                ", X",
+                Text::Generated {
+                    generator: "LOCGEN_TOUPPER".to_string(),
+                    args: vec![PropValue::Text(Text::Loc {
+                        key: "Key".to_string(),
+                        source: "Hi".to_string()
+                    })]
+                }
+            ))
+        );
+        assert_eq!(
+            text_literal(r#"LOCGEN_DATE_UTC(637000000000000000, EDateTimeStyle::Short, "", "en")"#)
+                .map(|r| r.1),
+            Ok(Text::Generated {
+                generator: "LOCGEN_DATE_UTC".to_string(),
+                args: vec![
+                    PropValue::Integer(637000000000000000),
+                    PropValue::Enum("EDateTimeStyle::Short".to_string()),
+                    PropValue::String("".to_string()),
+                    PropValue::String("en".to_string()),
+                ]
+            })
+        );
+        assert!(text_literal(r#"NSLOCTEXT("a", "b")"#).is_err());
+        assert!(text_literal(r#"INVTEXT(1)"#).is_err());
+        assert!(text_literal(r#"LOCTEXTS("a")"#).is_err());
     }
 
     #[test]
@@ -248,11 +409,11 @@ mod tests {
                     },
                     Prop {
                         key: "PinFriendlyName".to_owned(),
-                        value: PropValue::NslocText(
-                            "K2Node".to_owned(),
-                            "Target".to_owned(),
-                            "Target".to_owned()
-                        )
+                        value: PropValue::Text(Text::NsLoc {
+                            namespace: "K2Node".to_owned(),
+                            key: "Target".to_owned(),
+                            source: "Target".to_owned()
+                        })
                     },
                     Prop {
                         key: "PinToolTip".to_owned(),
@@ -266,10 +427,10 @@ mod tests {
                     },
                     Prop {
                         key: "PinType.PinSubCategoryObject".to_owned(),
-                        value: PropValue::ObjectReference(
-                            "Class".to_owned(),
-                            "/Script/Engine.GameplayStatics".to_owned()
-                        )
+                        value: PropValue::ObjectReference {
+                            class: "Class".to_owned(),
+                            path: ObjectPath::from("/Script/Engine.GameplayStatics")
+                        }
                     },
                     Prop {
                         key: "PinType.PinSubCategoryMemberReference".to_owned(),
@@ -280,10 +441,7 @@ mod tests {
                         value: PropValue::PropList(vec![
                             Prop {
                                 key: "PinType.ContainerType".to_owned(),
-                                value: PropValue::ObjectReference(
-                                    "None".to_owned(),
-                                    "None".to_owned()
-                                )
+                                value: PropValue::Enum("None".to_owned())
                             },
                             Prop {
                                 key: "PinType.bIsReference".to_owned(),
@@ -294,10 +452,7 @@ mod tests {
                                 value: PropValue::PropList(vec![
                                     Prop {
                                         key: "PinType.ContainerType".to_owned(),
-                                        value: PropValue::ObjectReference(
-                                            "None".to_owned(),
-                                            "None".to_owned()
-                                        )
+                                        value: PropValue::Enum("None".to_owned())
                                     },
                                     Prop {
                                         key: "PinType.bIsReference".to_owned(),
@@ -315,10 +470,12 @@ mod tests {
                     },
                     Prop {
                         key: "MemberParent".to_owned(),
-                        value: PropValue::ObjectReference(
-                            "BlueprintGeneratedClass".to_owned(),
-                            "/Game/Blueprints/PlayerCharacter.PlayerCharacter_C".to_owned()
-                        )
+                        value: PropValue::ObjectReference {
+                            class: "BlueprintGeneratedClass".to_owned(),
+                            path: ObjectPath::from(
+                                "/Game/Blueprints/PlayerCharacter.PlayerCharacter_C"
+                            )
+                        }
                     },
                     Prop {
                         key: "LinkedTo".to_owned(),
@@ -359,6 +516,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_array_literal() {
+        assert_eq!(
+            array_literal(r#"("A", "B",)"#),
+            Ok((
+                "",
+                vec![
+                    PropValue::String("A".to_owned()),
+                    PropValue::String("B".to_owned())
+                ]
+            ))
+        );
+        assert_eq!(
+            array_literal("((X=1,Y=2),(X=3,Y=4))"),
+            Ok((
+                "",
+                [(1, 2), (3, 4)]
+                    .iter()
+                    .map(|(x, y)| PropValue::PropList(vec![
+                        Prop {
+                            key: "X".to_owned(),
+                            value: PropValue::Integer(*x)
+                        },
+                        Prop {
+                            key: "Y".to_owned(),
+                            value: PropValue::Integer(*y)
+                        },
+                    ]))
+                    .collect()
+            ))
+        );
+        assert!(array_literal("()").is_err());
+        assert_eq!(
+            prop_value("(1,2,3)"),
+            Ok((
+                "",
+                PropValue::Array(vec![
+                    PropValue::Integer(1),
+                    PropValue::Integer(2),
+                    PropValue::Integer(3)
+                ])
+            ))
+        );
+        assert_eq!(prop_value("()"), Ok(("", PropValue::PropList(Vec::new()))));
+        assert!(matches!(
+            prop_value("(K2Node_Knot_0 7CD635904148E54F000DA597BA60AB39,)"),
+            Ok(("", PropValue::LinkedToList(_)))
+        ));
+    }
+
+    #[test]
+    fn parse_identifier() {
+        for id in [
+            "EGPD_Output",
+            "NotSelfContext",
+            "_Private1",
+            "ECollisionChannel::ECC_Visibility",
+            "EObjectFlags.RF_Public",
+        ] {
+            assert_eq!(identifier(id), Ok(("", id)));
+        }
+        assert_eq!(identifier("Array,"), Ok((",", "Array")));
+        assert_eq!(identifier("EFoo::"), Ok(("::", "EFoo")));
+        assert!(identifier("1abc").is_err());
+        assert_eq!(boolean("True,"), Ok((",", true)));
+        assert!(boolean("TrueColor").is_err());
+    }
+
+    #[test]
+    fn parse_object_literal() {
+        assert_eq!(
+            object_literal(r#"Class'"/Script/Engine.Actor"'"#),
+            Ok((
+                "",
+                ("Class".to_owned(), ObjectPath::from("/Script/Engine.Actor"))
+            ))
+        );
+        assert_eq!(
+            object_literal("SceneComponent'/Script/Engine.Default__CameraActor:SceneComponent',"),
+            Ok((
+                ",",
+                (
+                    "SceneComponent".to_owned(),
+                    ObjectPath::from("/Script/Engine.Default__CameraActor:SceneComponent")
+                )
+            ))
+        );
+        assert_eq!(
+            object_literal(
+                "/Script/Engine.CameraActor'/Script/Engine.Default__CameraActor' Next=1"
+            ),
+            Ok((
+                " Next=1",
+                (
+                    "/Script/Engine.CameraActor".to_owned(),
+                    ObjectPath::from("/Script/Engine.Default__CameraActor")
+                )
+            ))
+        );
+        assert!(object_literal("Class'Actor'").is_err());
+    }
+
+    #[test]
+    fn parse_double() {
+        for (text, value) in [
+            ("330.000000", 330.0),
+            ("-560.123400", -560.1234),
+            ("-0.5", -0.5),
+            ("+1.25", 1.25),
+            (".5", 0.5),
+            ("1.", 1.0),
+            ("1e3", 1000.0),
+            ("-2.5E-2", -0.025),
+            ("0.1", 0.1),
+        ] {
+            let (rest, d) = double(text).unwrap();
+            assert_eq!(rest, "");
+            assert_eq!(d.value(), value, "{}", text);
+            assert_eq!(d.text(), text);
+        }
+        assert_eq!(
+            double("-0.0").unwrap().1.value().to_bits(),
+            (-0.0f64).to_bits()
+        );
+        assert_eq!(double("1.5,X=1").unwrap().0, ",X=1");
+        assert!(double("42").is_err());
+        assert!(double("-").is_err());
+        assert!(double("e5").is_err());
+    }
+
     #[test]
     fn parse_string_literal() {
         assert_eq!(
@@ -386,7 +673,7 @@ mod tests {
         );
         assert_eq!(
             uuid_literal("914EEC5D4C41B6D45E6DB79302BCC5BAFFF"),
-            Err(Err::Error(Error::new(
+            Err(Err::Error(SyntaxError::from_error_kind(
                 "914EEC5D4C41B6D45E6DB79302BCC5BAFFF",
                 ErrorKind::Fail
             )))
@@ -407,14 +694,17 @@ mod tests {
         );
         assert_eq!(
             uuid_literal("MMMMMMMMMMMMMMMMMMMMMMMMMMMMC5BA"),
-            Err(Err::Error(Error::new(
+            Err(Err::Error(SyntaxError::from_error_kind(
                 "MMMMMMMMMMMMMMMMMMMMMMMMMMMMC5BA",
                 ErrorKind::TakeWhileMN
             )))
         );
         assert_eq!(
             uuid_literal("49B87A6D2A"),
-            Err(Err::Error(Error::new("49B87A6D2A", ErrorKind::TakeWhileMN)))
+            Err(Err::Error(SyntaxError::from_error_kind(
+                "49B87A6D2A",
+                ErrorKind::TakeWhileMN
+            )))
         );
     }
 }
@@ -0,0 +1,150 @@
+use nom::{
+    branch::alt,
+    character::complete::{line_ending, multispace0, space0},
+    combinator::{consumed, map, opt, recognize},
+    multi::many1,
+    sequence::pair,
+    Err, Parser,
+};
+
+use super::{
+    cst::{CstElement, CstObject, Document, Line, Trivia},
+    diagnostic::{IResult, SyntaxError},
+    object::{object_begin, object_end},
+    prop::{prop_custom_props, prop_kv},
+};
+
+/// Wraps a parser of a line's content so that the surrounding whitespace and the original text
+/// are kept.
+pub fn line<'a, T, P>(mut parser: P) -> impl FnMut(&'a str) -> IResult<&'a str, Line<T>>
+where
+    T: Clone + PartialEq,
+    P: Parser<&'a str, T, SyntaxError<&'a str>>,
+{
+    move |s: &'a str| {
+        let (s, leading) = multispace0(s)?;
+        let (s, (text, value)) = consumed(|s| parser.parse(s))(s)?;
+        // Some content parsers consume the line ending themselves.
+        let (s, after) = if text.ends_with('\n') {
+            (s, "")
+        } else {
+            recognize(pair(space0, opt(line_ending)))(s)?
+        };
+        let content = text.trim_end();
+        Ok((
+            s,
+            Line::parsed(
+                value,
+                content,
+                Trivia {
+                    leading: leading.to_owned(),
+                    trailing: format!("{}{}", &text[content.len()..], after),
+                },
+            ),
+        ))
+    }
+}
+
+/// Parser for the elements of an object, keeping their formatting.
+pub fn cst_object_content(s: &str) -> IResult<&str, Vec<CstElement>> {
+    let mut remain = s;
+    let mut elements = Vec::new();
+    while let Ok((r, e)) = alt((
+        map(line(prop_kv), CstElement::Prop),
+        map(line(prop_custom_props), CstElement::CustomProp),
+        map(cst_object, CstElement::Object),
+    ))(remain)
+    {
+        remain = r;
+        elements.push(e);
+    }
+    Ok((remain, elements))
+}
+
+/// Parse for a single object, keeping its formatting.
+pub fn cst_object(s: &str) -> IResult<&str, CstObject> {
+    let (remain, begin) = line(object_begin)(s)?;
+    let (end_line, elements) = cst_object_content(remain)?;
+    let (remain, end) = line(object_end)(end_line)?;
+    if end.value.object_type != begin.value.object_type {
+        let expected = format!("`End {}`", begin.value.object_type);
+        return Err(Err::Error(SyntaxError::expected(end_line, expected)));
+    }
+    Ok((
+        remain,
+        CstObject {
+            begin,
+            elements,
+            end,
+        },
+    ))
+}
+
+/// Parser for a whole document, keeping its formatting.
+pub fn document(s: &str) -> IResult<&str, Document> {
+    let line_ending = if s.contains("\r\n") { "\r\n" } else { "\n" };
+    let (remain, (objects, trailing)) = pair(many1(cst_object), multispace0)(s)?;
+    Ok((
+        remain,
+        Document {
+            objects,
+            trailing: trailing.to_owned(),
+            line_ending: line_ending.to_owned(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{
+        ast::{ObjectHeader, Prop, PropValue},
+        object::objects,
+    };
+
+    #[test]
+    fn parse_line_trivia() {
+        let (remain, l) = line(prop_kv)("\n   NodePosX=512  \r\n   NodePosY=-16").unwrap();
+        assert_eq!(remain, "   NodePosY=-16");
+        assert_eq!(l.source_text(), Some("NodePosX=512"));
+        assert_eq!(
+            l.trivia,
+            Some(Trivia {
+                leading: "\n   ".to_owned(),
+                trailing: "  \r\n".to_owned()
+            })
+        );
+
+        let (remain, l) = line(object_begin)("Begin Object Name=\"A\"\r\nEnd Object").unwrap();
+        assert_eq!(remain, "End Object");
+        assert_eq!(l.source_text(), Some("Begin Object Name=\"A\""));
+        assert_eq!(l.trivia.unwrap().trailing, "\r\n");
+    }
+
+    #[test]
+    fn parse_document() {
+        let sample = "Begin Object Name=\"A\"\r\n   X=-560.000000\r\n\r\n   Begin Object\r\n   End Object\r\nEnd Object\r\n\r\n";
+        let (remain, doc) = document(sample).unwrap();
+        assert_eq!(remain, "");
+        assert_eq!(doc.line_ending, "\r\n");
+        assert_eq!(doc.trailing, "\r\n");
+        assert_eq!(doc.to_objects(), objects(sample).unwrap().1);
+        assert_eq!(
+            doc.objects[0].begin.value,
+            ObjectHeader {
+                object_type: "Object".to_owned(),
+                header_props: vec![Prop {
+                    key: "Name".to_owned(),
+                    value: PropValue::String("A".to_owned())
+                }]
+            }
+        );
+        match &doc.objects[0].elements[1] {
+            CstElement::Object(o) => {
+                assert_eq!(o.begin.trivia.as_ref().unwrap().leading, "\r\n   ")
+            }
+            e => panic!("unexpected element {:?}", e),
+        }
+        assert!(document("Begin Object\nEnd Level\n").is_err());
+    }
+}
@@ -2,66 +2,123 @@ use crate::parser::prop::prop_custom_props;
 
 use super::{
     ast::{Object, ObjectElement, ObjectEnd, ObjectHeader, Objects, Prop, PropValue},
-    prop::prop_kv,
+    diagnostic::{IResult, SyntaxError},
+    literal::{identifier, is_identifier_char, object_literal, object_path, string_literal},
+    prop::{prop_key, prop_kv, prop_value},
+    schema,
 };
 use nom::{
     branch::{alt, permutation},
-    bytes::complete::{tag, take_until1},
-    character::complete::{alphanumeric1, line_ending, multispace0, space0},
-    combinator::{eof, map, opt, recognize},
-    multi::{many1, separated_list0},
-    IResult,
+    bytes::complete::{tag, take_till1, take_while1},
+    character::complete::{
+        alphanumeric1, char, line_ending, multispace0, not_line_ending, space0, space1,
+    },
+    combinator::{eof, map, map_opt, peek, recognize},
+    error::context,
+    multi::{many1, many_till},
+    sequence::{pair, preceded, terminated, tuple},
+    Err,
 };
 
-/// Parser for object elements (Key / Value properties, custom properties).
+/// Parser for an object element (Key / Value property, custom properties, nested object).
+pub fn object_element(s: &str) -> IResult<&str, ObjectElement> {
+    alt((
+        map(prop_kv, ObjectElement::Prop),
+        map(prop_custom_props, ObjectElement::CustomProp),
+        map(object, ObjectElement::Object),
+    ))(s)
+}
+
+/// Parser for object elements. Stops at the end of the input or at an `End` line, and fails on
+/// any other line that is not an element.
 pub fn object_content(s: &str) -> IResult<&str, Vec<ObjectElement>> {
     let mut remain = s;
     let mut retobj = Vec::new();
 
     loop {
-        let r = opt(permutation((
-            multispace0,
-            alt((
-                map(prop_kv, |v| ObjectElement::Prop(v)),
-                map(prop_custom_props, |v| ObjectElement::CustomProp(v)),
-                // map(object, |v| {
-                //     ObjectElement::Object(v)
-                // }),
-            )),
-            multispace0,
-        )))(remain)
-        .unwrap();
-        if let Some(p) = r.1 {
-            remain = r.0;
-            retobj.push(p.1);
-            continue;
+        let (p, _) = multispace0(remain)?;
+        match object_element(p) {
+            Ok((r, e)) => {
+                remain = r;
+                retobj.push(e);
+            }
+            Err(Err::Error(_)) if p.is_empty() || p.starts_with("End ") => break,
+            Err(e) => return Err(e),
         }
-        break;
     }
     Ok((remain, retobj))
 }
 
-/// Parse for object start line.
-pub fn object_begin(s: &str) -> IResult<&str, ObjectHeader> {
-    let (remain, (_, object_type)) = permutation((tag("Begin "), alphanumeric1))(s)?;
-
-    let (remain, (_, props, _)) = permutation((
-        space0,
-        separated_list0(
-            tag(" "),
-            alt((
+/// Parser for the value of a header property. Header values never contain spaces, and `Class`
+/// and `Name` may be written without quotes. A quoted `ExportPath` of UE5 is read as the object
+/// reference it contains.
+pub fn header_value<'a>(key: &str) -> impl FnMut(&'a str) -> IResult<&'a str, PropValue> {
+    let key = key.to_owned();
+    move |s: &'a str| {
+        let end = || peek(alt((space1, eof)));
+        match key.as_str() {
+            "Class" => alt((
+                map(string_literal, PropValue::String),
+                map(alt((object_path, identifier)), |v: &str| {
+                    PropValue::String(v.to_owned())
+                }),
+            ))(s),
+            "Name" => alt((
+                map(string_literal, PropValue::String),
                 map(
-                    permutation((tag("Class="), take_until1(" "))),
-                    |v: (_, &str)| Prop {
-                        key: "Class".to_owned(),
-                        value: PropValue::String(v.1.to_owned()),
-                    },
+                    take_while1(|c| is_identifier_char(c) || c == '.' || c == ':'),
+                    |v: &str| PropValue::String(v.to_owned()),
                 ),
-                prop_kv,
-            )),
+            ))(s),
+            "Archetype" | "ExportPath" => alt((
+                map(object_literal, |(class, path)| PropValue::ObjectReference {
+                    class,
+                    path,
+                }),
+                map_opt(string_literal, |v| match object_literal(&v) {
+                    Ok(("", (class, path))) => Some(PropValue::ObjectReference { class, path }),
+                    _ => None,
+                }),
+                map(string_literal, PropValue::String),
+            ))(s),
+            _ => alt((
+                terminated(prop_value, end()),
+                map(take_till1(char::is_whitespace), |v: &str| {
+                    PropValue::Other(v.to_owned())
+                }),
+            ))(s),
+        }
+    }
+}
+
+/// Parser for a `Key=Value` property of an object start line.
+pub fn header_prop(s: &str) -> IResult<&str, Prop> {
+    let (remain, (key, _)) = pair(
+        context("property key", prop_key),
+        context("`=` after property key", char('=')),
+    )(s)?;
+    let (remain, value) = context("property value", header_value(key))(remain)?;
+    Ok((
+        remain,
+        Prop {
+            key: key.to_owned(),
+            value: schema::interpret(key, value),
+        },
+    ))
+}
+
+/// Parse for object start line. The header properties must all be on this line.
+pub fn object_begin(s: &str) -> IResult<&str, ObjectHeader> {
+    peek(context("`Begin <Type>`", tag("Begin ")))(s)?;
+    let (remain, (line, _)) = pair(not_line_ending, context("line ending", line_ending))(s)?;
+    let (_, (_, object_type, (props, _))) = tuple((
+        tag("Begin "),
+        context("object type", alphanumeric1),
+        many_till(
+            preceded(context("space or line ending", space1), header_prop),
+            pair(space0, eof),
         ),
-        line_ending,
-    ))(remain)?;
+    ))(line)?;
 
     Ok((
         remain,
@@ -78,7 +135,7 @@ pub fn object_end(s: &str) -> IResult<&str, ObjectEnd> {
         permutation((
             tag("End "),
             alphanumeric1,
-            alt((recognize(line_ending), recognize(eof))),
+            context("line ending", alt((recognize(line_ending), recognize(eof)))),
         )),
         |v: (_, &str, _)| ObjectEnd {
             object_type: v.1.to_owned(),
@@ -87,21 +144,35 @@ pub fn object_end(s: &str) -> IResult<&str, ObjectEnd> {
 }
 
 /// Parse for a single object.
+///
+/// Child `Begin X ... End X` blocks are parsed recursively, and the type of every end line must
+/// match the type of its begin line.
 pub fn object(s: &str) -> IResult<&str, Object> {
-    map(
-        permutation((object_begin, object_content, object_end)),
-        |v| Object {
-            header: v.0,
-            elements: v.1,
-        },
-    )(s)
+    let (remain, header) = object_begin(s)?;
+    let name = header
+        .header_props
+        .iter()
+        .find(|p| p.key == "Name")
+        .and_then(|p| p.value.as_str());
+    let (end_line, (elements, _)) =
+        pair(object_content, multispace0)(remain).map_err(|e| e.map(|e| e.in_object(name)))?;
+    let remain = match object_end(end_line) {
+        Ok((remain, end)) if end.object_type == header.object_type => remain,
+        Err(Err::Error(e)) if e.expected.is_some() => return Err(Err::Error(e.in_object(name))),
+        _ => {
+            let expected = format!("`End {}`", header.object_type);
+            let e = SyntaxError::expected(end_line, expected).in_object(name);
+            return Err(Err::Error(e));
+        }
+    };
+    Ok((remain, Object { header, elements }))
 }
 
 /// Parser for multiple objects.
 pub fn objects(s: &str) -> IResult<&str, Objects> {
     map(
         many1(map(permutation((object, multispace0)), |v| v.0)),
-        |v| Objects(v),
+        Objects,
     )(s)
 }
 
@@ -109,12 +180,131 @@ pub fn objects(s: &str) -> IResult<&str, Objects> {
 mod tests {
     use super::*;
     use crate::parser::ast::{
-        CustomProp, CustomPropValue, LinkedTo, ObjectElement, ObjectHeader, Prop, PropValue,
+        CustomProp, CustomPropValue, LinkedTo, ObjectElement, ObjectHeader, ObjectPath, Prop,
+        PropValue,
     };
     use uuid::Uuid;
 
     #[test]
     fn parse_nested_object() {
+        let sample = r#"Begin Map
+   Begin Level
+      Begin Actor Class=/Script/Engine.CameraActor Name="CameraActor_13"
+         Begin Object Class=/Script/Engine.SceneComponent Name="SceneComponent"
+         End Object
+         Begin Object Name="SceneComponent"
+            RelativeLocation=(X=-560,Y=-1080,Z=330)
+         End Object
+         ActorLabel="CameraActor"
+      End Actor
+   End Level
+Begin Surface
+End Surface
+End Map
+"#;
+        let scene_component = Object {
+            header: ObjectHeader {
+                object_type: "Object".to_owned(),
+                header_props: vec![
+                    Prop {
+                        key: "Class".to_owned(),
+                        value: PropValue::String("/Script/Engine.SceneComponent".to_owned()),
+                    },
+                    Prop {
+                        key: "Name".to_owned(),
+                        value: PropValue::String("SceneComponent".to_owned()),
+                    },
+                ],
+            },
+            elements: Vec::new(),
+        };
+        let scene_component_props = Object {
+            header: ObjectHeader {
+                object_type: "Object".to_owned(),
+                header_props: vec![Prop {
+                    key: "Name".to_owned(),
+                    value: PropValue::String("SceneComponent".to_owned()),
+                }],
+            },
+            elements: vec![ObjectElement::Prop(Prop {
+                key: "RelativeLocation".to_owned(),
+                value: PropValue::PropList(vec![
+                    Prop {
+                        key: "X".to_owned(),
+                        value: PropValue::Integer(-560),
+                    },
+                    Prop {
+                        key: "Y".to_owned(),
+                        value: PropValue::Integer(-1080),
+                    },
+                    Prop {
+                        key: "Z".to_owned(),
+                        value: PropValue::Integer(330),
+                    },
+                ]),
+            })],
+        };
+        let actor = Object {
+            header: ObjectHeader {
+                object_type: "Actor".to_owned(),
+                header_props: vec![
+                    Prop {
+                        key: "Class".to_owned(),
+                        value: PropValue::String("/Script/Engine.CameraActor".to_owned()),
+                    },
+                    Prop {
+                        key: "Name".to_owned(),
+                        value: PropValue::String("CameraActor_13".to_owned()),
+                    },
+                ],
+            },
+            elements: vec![
+                ObjectElement::Object(scene_component),
+                ObjectElement::Object(scene_component_props),
+                ObjectElement::Prop(Prop {
+                    key: "ActorLabel".to_owned(),
+                    value: PropValue::String("CameraActor".to_owned()),
+                }),
+            ],
+        };
+        assert_eq!(
+            object(sample),
+            Ok((
+                "",
+                Object {
+                    header: ObjectHeader {
+                        object_type: "Map".to_owned(),
+                        header_props: Vec::new()
+                    },
+                    elements: vec![
+                        ObjectElement::Object(Object {
+                            header: ObjectHeader {
+                                object_type: "Level".to_owned(),
+                                header_props: Vec::new()
+                            },
+                            elements: vec![ObjectElement::Object(actor)]
+                        }),
+                        ObjectElement::Object(Object {
+                            header: ObjectHeader {
+                                object_type: "Surface".to_owned(),
+                                header_props: Vec::new()
+                            },
+                            elements: Vec::new()
+                        }),
+                    ]
+                }
+            ))
+        );
+
+        assert!(object("Begin Map\n   Begin Level\n   End Map\nEnd Level\n").is_err());
+        let sample = "Begin Level\nEnd Map\n";
+        assert_eq!(
+            object(sample),
+            Err(Err::Error(SyntaxError::expected(
+                "End Map\n",
+                "`End Level`"
+            )))
+        );
     }
 
     #[test]
@@ -197,10 +387,7 @@ End Object
                                 },
                                 Prop {
                                     key: "PinType.PinSubCategoryObject".to_owned(),
-                                    value: PropValue::ObjectReference(
-                                        "None".to_owned(),
-                                        "None".to_owned()
-                                    )
+                                    value: PropValue::None
                                 },
                                 Prop {
                                     key: "PinType.PinValueType".to_owned(),
@@ -217,10 +404,10 @@ End Object
                             value: CustomPropValue::Pin(vec![
                                 Prop {
                                     key: "PinType.PinSubCategoryObject".to_owned(),
-                                    value: PropValue::ObjectReference(
-                                        "Class".to_owned(),
-                                        "/Script/UMG.Button".to_owned()
-                                    )
+                                    value: PropValue::ObjectReference {
+                                        class: "Class".to_owned(),
+                                        path: ObjectPath::from("/Script/UMG.Button")
+                                    }
                                 },
                                 Prop {
                                     key: "PinType.PinSubCategoryMemberReference".to_owned(),
@@ -228,10 +415,7 @@ End Object
                                 },
                                 Prop {
                                     key: "PinType.ContainerType".to_owned(),
-                                    value: PropValue::ObjectReference(
-                                        "None".to_owned(),
-                                        "None".to_owned()
-                                    )
+                                    value: PropValue::Enum("None".to_owned())
                                 },
                                 Prop {
                                     key: "PinType.bIsReference".to_owned(),
@@ -360,6 +544,53 @@ End Object
                 }
             ))
         );
+        assert_eq!(
+            object_begin(
+                "Begin Actor Class=/Script/Engine.CameraActor Name=CameraActor_13 Archetype=/Script/Engine.CameraActor'/Script/Engine.Default__CameraActor'\n"
+            ),
+            Ok((
+                "",
+                ObjectHeader {
+                    object_type: "Actor".to_owned(),
+                    header_props: vec![
+                        Prop {
+                            key: "Class".to_owned(),
+                            value: PropValue::String("/Script/Engine.CameraActor".to_owned())
+                        },
+                        Prop {
+                            key: "Name".to_owned(),
+                            value: PropValue::String("CameraActor_13".to_owned())
+                        },
+                        Prop {
+                            key: "Archetype".to_owned(),
+                            value: PropValue::ObjectReference {
+                                class: "/Script/Engine.CameraActor".to_owned(),
+                                path: ObjectPath::from("/Script/Engine.Default__CameraActor")
+                            }
+                        }
+                    ]
+                }
+            ))
+        );
+        assert_eq!(
+            object_begin("Begin Object Name=Foo.Bar:Baz ExportPath=\"Class'/Pkg/A.A:B'\"\n")
+                .map(|(_, h)| h.header_props),
+            Ok(vec![
+                Prop {
+                    key: "Name".to_owned(),
+                    value: PropValue::String("Foo.Bar:Baz".to_owned())
+                },
+                Prop {
+                    key: "ExportPath".to_owned(),
+                    value: PropValue::ObjectReference {
+                        class: "Class".to_owned(),
+                        path: ObjectPath::from("/Pkg/A.A:B")
+                    }
+                }
+            ])
+        );
+        assert!(object_begin("Begin Object Name=\"A\"\n   X=1\n").is_ok());
+        assert!(object_begin("Begin Object Name=\"A\" X\n").is_err());
     }
 
     #[test]
@@ -408,10 +639,7 @@ End Object
                             },
                             Prop {
                                 key: "PinType.PinSubCategoryObject".to_owned(),
-                                value: PropValue::ObjectReference(
-                                    "None".to_owned(),
-                                    "None".to_owned()
-                                )
+                                value: PropValue::None
                             },
                             Prop {
                                 key: "PinType.PinValueType".to_owned(),
@@ -428,10 +656,10 @@ End Object
                         value: CustomPropValue::Pin(vec![
                             Prop {
                                 key: "PinType.PinSubCategoryObject".to_owned(),
-                                value: PropValue::ObjectReference(
-                                    "Class".to_owned(),
-                                    "/Script/UMG.Button".to_owned()
-                                )
+                                value: PropValue::ObjectReference {
+                                    class: "Class".to_owned(),
+                                    path: ObjectPath::from("/Script/UMG.Button")
+                                }
                             },
                             Prop {
                                 key: "PinType.PinSubCategoryMemberReference".to_owned(),
@@ -439,10 +667,7 @@ End Object
                             },
                             Prop {
                                 key: "PinType.ContainerType".to_owned(),
-                                value: PropValue::ObjectReference(
-                                    "None".to_owned(),
-                                    "None".to_owned()
-                                )
+                                value: PropValue::Enum("None".to_owned())
                             },
                             Prop {
                                 key: "PinType.bIsReference".to_owned(),
@@ -1,50 +1,85 @@
 use nom::{
-    branch::{alt, permutation},
-    bytes::complete::{tag, take_till1, take_while1},
+    branch::alt,
+    bytes::complete::{tag, take_till1, take_while, take_while1},
     character::{
         complete,
-        complete::{alphanumeric1, char, multispace0, space1},
+        complete::{char, digit1, multispace0, satisfy, space1},
         is_newline, is_space,
     },
-    combinator::map,
-    error::{Error, ErrorKind},
-    Err, IResult,
+    combinator::{map, map_res, not, recognize},
+    error::context,
+    sequence::{preceded, terminated, tuple},
 };
 
 use super::{
-    ast::{CustomProp, CustomPropValue, Prop, PropValue},
+    ast::{CustomProp, CustomPropValue, Prop, PropValue, StaticArrayElement},
+    diagnostic::IResult,
     literal::{
-        boolean, double, kv_list_literal, linkedto_list_literal, nsloc_text_literal,
-        object_literal, string_literal, uuid_literal,
+        array_literal, boolean, double, identifier, is_identifier_char, kv_list_literal,
+        linkedto_list_literal, none_literal, object_literal, string_literal, text_literal,
+        uuid_literal,
     },
+    schema,
 };
 
 /// A parser for any PropValue of an object.
+///
+/// Values whose type depends on the property, such as 32 hex digits that may be a GUID, are kept
+/// as `PropValue::Other` here and typed by `prop_kv` according to the key.
 pub fn prop_value(s: &str) -> IResult<&str, PropValue> {
     alt((
-        map(boolean, |v| PropValue::Boolean(v)),
-        map(uuid_literal, |v| PropValue::Uuid(v)),
-        map(string_literal, |v| PropValue::String(v)),
-        map(nsloc_text_literal, |v| PropValue::NslocText(v.0, v.1, v.2)),
-        map(object_literal, |v| PropValue::ObjectReference(v.0, v.1)),
-        map(double, |v| PropValue::Double(v)),
-        map(complete::i64, |v| PropValue::Integer(v)),
-        map(kv_list_literal, |v| PropValue::PropList(v)),
-        map(linkedto_list_literal, |v| PropValue::LinkedToList(v)),
-        map(alphanumeric1, |v: &str| PropValue::Other(v.to_string())),
+        map(boolean, PropValue::Boolean),
+        map(none_literal, |_| PropValue::None),
+        map(
+            terminated(recognize(uuid_literal), not(satisfy(is_identifier_char))),
+            |v: &str| PropValue::Other(v.to_string()),
+        ),
+        map(string_literal, PropValue::String),
+        map(text_literal, PropValue::Text),
+        map(object_literal, |(class, path)| PropValue::ObjectReference {
+            class,
+            path,
+        }),
+        map(double, PropValue::Double),
+        map(complete::i64, PropValue::Integer),
+        map(kv_list_literal, PropValue::PropList),
+        map(linkedto_list_literal, PropValue::LinkedToList),
+        map(array_literal, PropValue::Array),
+        map(identifier, |v: &str| PropValue::Enum(v.to_string())),
     ))(s)
 }
 
+/// A parser for property keys. Stops before a static array index such as `(0)`.
+pub fn prop_key(s: &str) -> IResult<&str, &str> {
+    take_till1(|c: char| is_space(c as u8) || "=(),".contains(c))(s)
+}
+
+/// A parser for the index of a static array element, such as `(0)`.
+pub fn prop_index(s: &str) -> IResult<&str, usize> {
+    preceded(
+        char('('),
+        context(
+            "array index followed by `)`",
+            terminated(map_res(digit1, str::parse), char(')')),
+        ),
+    )(s)
+}
+
 /// A parser for any object Prop in key/value format.
 pub fn prop_kv(s: &str) -> IResult<&str, Prop> {
-    let (s, (_, key, _, _, _, value)) = permutation((
+    let (s, (_, key, index, _, _, value)) = tuple((
         multispace0,
-        take_till1(|c: char| is_space(c as u8) || c == '='),
+        context("property key", prop_key),
+        alt((map(prop_index, Some), map(not(char('(')), |_| None))),
+        context("`=` after property key", preceded(multispace0, char('='))),
         multispace0,
-        char('='),
-        multispace0,
-        prop_value,
+        context("property value", prop_value),
     ))(s)?;
+    let value = schema::interpret(key, value);
+    let value = match index {
+        Some(index) => PropValue::StaticArray(vec![StaticArrayElement { index, value }]),
+        None => value,
+    };
     Ok((
         s,
         Prop {
@@ -54,24 +89,52 @@ pub fn prop_kv(s: &str) -> IResult<&str, Prop> {
     ))
 }
 
+/// A parser for the name of a CustomProperties domain.
+pub fn custom_props_domain(s: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(s)
+}
+
 /// A parser for the CustomProperties syntax.
 pub fn prop_custom_props(s: &str) -> IResult<&str, CustomProp> {
-    let (ns, (_, _, name, _)) =
-        permutation((tag("CustomProperties"), space1, alphanumeric1, space1))(s)?;
-    let (ns, prop_code) = take_while1(|c| !is_newline(c as u8))(ns)?;
-    let custom_prop = match name {
-        "Pin" => map(kv_list_literal, |v| CustomProp {
-            domain: name.to_string(),
-            value: CustomPropValue::Pin(v),
-        })(prop_code)?,
-        _ => return Err(Err::Error(Error::new(s, ErrorKind::Fail))),
+    let (ns, (_, _, name)) = tuple((
+        tag("CustomProperties"),
+        space1,
+        context("custom properties domain", custom_props_domain),
+    ))(s)?;
+    let (ns, prop_code) = take_while(|c| !is_newline(c as u8))(ns)?;
+    let payload = || {
+        context(
+            "custom properties payload",
+            preceded(space1, kv_list_literal),
+        )
+    };
+    let value = match name {
+        "Pin" => map(payload(), CustomPropValue::Pin)(prop_code)?.1,
+        "UserDefinedPin" => map(payload(), CustomPropValue::UserDefinedPin)(prop_code)?.1,
+        _ => CustomPropValue::Other(other_custom_props(prop_code.trim())),
     };
-    Ok((ns, custom_prop.1))
+    Ok((
+        ns,
+        CustomProp {
+            domain: name.to_string(),
+            value,
+        },
+    ))
+}
+
+/// Reads the payload of an unknown domain as a single value if possible, and as raw text
+/// otherwise.
+fn other_custom_props(s: &str) -> PropValue {
+    match prop_value(s) {
+        Ok(("", v)) => v,
+        _ => PropValue::Other(s.to_string()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::ast::{ObjectPath, Text};
     use assert_approx_eq::assert_approx_eq;
     use uuid::Uuid;
 
@@ -97,11 +160,11 @@ mod tests {
                         },
                         Prop {
                             key: "PinFriendlyName".to_owned(),
-                            value: PropValue::NslocText(
-                                "K2Node".to_owned(),
-                                "Target".to_owned(),
-                                "Target".to_owned()
-                            )
+                            value: PropValue::Text(Text::NsLoc {
+                                namespace: "K2Node".to_owned(),
+                                key: "Target".to_owned(),
+                                source: "Target".to_owned()
+                            })
                         },
                         Prop {
                             key: "PinType.PinCategory".to_owned(),
@@ -113,10 +176,10 @@ mod tests {
                         },
                         Prop {
                             key: "PinType.PinSubCategoryObject".to_owned(),
-                            value: PropValue::ObjectReference(
-                                "Class".to_owned(),
-                                "/Script/UMG.Button".to_owned()
-                            )
+                            value: PropValue::ObjectReference {
+                                class: "Class".to_owned(),
+                                path: ObjectPath::from("/Script/UMG.Button")
+                            }
                         },
                         Prop {
                             key: "PinType.PinSubCategoryMemberReference".to_owned(),
@@ -124,7 +187,7 @@ mod tests {
                         },
                         Prop {
                             key: "PinType.ContainerType".to_owned(),
-                            value: PropValue::ObjectReference("None".to_owned(), "None".to_owned())
+                            value: PropValue::Enum("None".to_owned())
                         },
                         Prop {
                             key: "PinType.bIsReference".to_owned(),
@@ -153,6 +216,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_custom_props_domains() {
+        let sample =
+            r#"CustomProperties UserDefinedPin (PinName="Speed",PinType=(PinCategory="float"))"#;
+        assert_eq!(
+            prop_custom_props(sample),
+            Ok((
+                "",
+                CustomProp {
+                    domain: "UserDefinedPin".to_string(),
+                    value: CustomPropValue::UserDefinedPin(vec![
+                        Prop {
+                            key: "PinName".to_owned(),
+                            value: PropValue::String("Speed".to_owned())
+                        },
+                        Prop {
+                            key: "PinType".to_owned(),
+                            value: PropValue::PropList(vec![Prop {
+                                key: "PinCategory".to_owned(),
+                                value: PropValue::String("float".to_owned())
+                            }])
+                        },
+                    ])
+                }
+            ))
+        );
+        assert_eq!(
+            prop_custom_props("CustomProperties MaterialExpression (Input=1,Output=2)\r\nX"),
+            Ok((
+                "\nX",
+                CustomProp {
+                    domain: "MaterialExpression".to_string(),
+                    value: CustomPropValue::Other(PropValue::PropList(vec![
+                        Prop {
+                            key: "Input".to_owned(),
+                            value: PropValue::Integer(1)
+                        },
+                        Prop {
+                            key: "Output".to_owned(),
+                            value: PropValue::Integer(2)
+                        },
+                    ]))
+                }
+            ))
+        );
+        assert_eq!(
+            prop_custom_props("CustomProperties Anim_State Entry -> Idle"),
+            Ok((
+                "",
+                CustomProp {
+                    domain: "Anim_State".to_string(),
+                    value: CustomPropValue::Other(PropValue::Other("Entry -> Idle".to_owned()))
+                }
+            ))
+        );
+        assert!(prop_custom_props("CustomProperties Pin (PinId=").is_err());
+    }
+
     #[test]
     fn parse_prop_kv_integer() {
         assert_eq!(
@@ -197,6 +318,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_prop_kv_in_order() {
+        assert!(prop_kv("=5 X").is_err());
+        assert!(prop_kv("=\"A\" B=1").is_err());
+    }
+
+    #[test]
+    fn parse_prop_kv_index() {
+        assert_eq!(
+            prop_kv(r#"OverrideMaterials(1)=Material'"/Game/M_Red.M_Red"'"#),
+            Ok((
+                "",
+                Prop {
+                    key: "OverrideMaterials".to_string(),
+                    value: PropValue::StaticArray(vec![StaticArrayElement {
+                        index: 1,
+                        value: PropValue::ObjectReference {
+                            class: "Material".to_string(),
+                            path: ObjectPath::from("/Game/M_Red.M_Red")
+                        }
+                    }])
+                }
+            ))
+        );
+        assert_eq!(
+            prop_kv("Offsets(0) = 3"),
+            Ok((
+                "",
+                Prop {
+                    key: "Offsets".to_string(),
+                    value: PropValue::StaticArray(vec![StaticArrayElement {
+                        index: 0,
+                        value: PropValue::Integer(3)
+                    }])
+                }
+            ))
+        );
+        assert!(prop_kv("Offsets(x)=3").is_err());
+    }
+
+    #[test]
+    fn parse_prop_kv_enum() {
+        assert_eq!(
+            prop_kv("TraceChannel=ECollisionChannel::ECC_Visibility"),
+            Ok((
+                "",
+                Prop {
+                    key: "TraceChannel".to_string(),
+                    value: PropValue::Enum("ECollisionChannel::ECC_Visibility".to_string())
+                }
+            ))
+        );
+        assert_eq!(
+            prop_value("EGPD_Output,"),
+            Ok((",", PropValue::Enum("EGPD_Output".to_string())))
+        );
+        assert_eq!(prop_value("1st"), Ok(("st", PropValue::Integer(1))));
+    }
+
     #[test]
     fn parse_prop_kv_boolean() {
         assert_eq!(
@@ -228,7 +408,7 @@ mod tests {
         assert_eq!(r.1.key, "X".to_owned());
         assert_approx_eq!(
             match r.1.value {
-                PropValue::Double(v) => v,
+                PropValue::Double(v) => v.value(),
                 _ => 0.0,
             },
             -560.123400
@@ -249,6 +429,23 @@ mod tests {
                 }
             ))
         );
+        assert_eq!(
+            prop_kv("Seed=00000000000000000000000000000000"),
+            Ok((
+                "",
+                Prop {
+                    key: "Seed".to_string(),
+                    value: PropValue::Other("00000000000000000000000000000000".to_string())
+                }
+            ))
+        );
+        assert_eq!(
+            prop_value("ABCDEF0123456789ABCDEF0123456789_Name"),
+            Ok((
+                "",
+                PropValue::Enum("ABCDEF0123456789ABCDEF0123456789_Name".to_string())
+            ))
+        );
     }
 
     #[test]
@@ -283,10 +480,10 @@ mod tests {
                 "",
                 Prop {
                     key: "PinType.PinSubCategoryObject".to_string(),
-                    value: PropValue::ObjectReference(
-                        "Class".to_string(),
-                        "/Script/Engine.GameplayStatics".to_string()
-                    )
+                    value: PropValue::ObjectReference {
+                        class: "Class".to_string(),
+                        path: ObjectPath::from("/Script/Engine.GameplayStatics")
+                    }
                 }
             ))
         );
@@ -296,10 +493,24 @@ mod tests {
                 "",
                 Prop {
                     key: "PinType.PinSubCategoryObject".to_string(),
-                    value: PropValue::ObjectReference("None".to_string(), "None".to_string())
+                    value: PropValue::None
+                }
+            ))
+        );
+        assert_eq!(
+            prop_kv("PinType.ContainerType=Array"),
+            Ok((
+                "",
+                Prop {
+                    key: "PinType.ContainerType".to_string(),
+                    value: PropValue::Enum("Array".to_string())
                 }
             ))
         );
+        assert_eq!(
+            prop_value("NoneSuch"),
+            Ok(("", PropValue::Enum("NoneSuch".to_string())))
+        );
     }
 
     #[test]
@@ -310,11 +521,11 @@ mod tests {
                 "",
                 Prop {
                     key: "PinFriendlyName".to_string(),
-                    value: PropValue::NslocText(
-                        "K2Node".to_string(),
-                        "Target".to_string(),
-                        "Target".to_string()
-                    )
+                    value: PropValue::Text(Text::NsLoc {
+                        namespace: "K2Node".to_string(),
+                        key: "Target".to_string(),
+                        source: "Target".to_string()
+                    })
                 }
             ))
         );
@@ -0,0 +1,259 @@
+use nom::{branch::alt, character::complete::multispace0, combinator::map};
+
+use crate::error::ParseError;
+
+use super::{
+    ast::{Object, ObjectElement, Objects},
+    diagnostic::{diagnose_line, diagnose_object},
+    object::{object_begin, object_end},
+    prop::{prop_custom_props, prop_kv},
+};
+
+/// The objects that could be parsed, and the errors of the objects that were skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    pub objects: Objects,
+    pub diagnostics: Vec<ParseError>,
+}
+
+/// Parser for multiple objects that skips malformed objects instead of failing.
+///
+/// When an object cannot be parsed, an error is recorded and parsing resumes at the next
+/// `Begin` or `End` line. A malformed object is dropped as a whole, while its parent and
+/// siblings are kept.
+pub fn objects_tolerant(source: &str) -> Recovered {
+    let mut diagnostics = Vec::new();
+    let mut objects = Vec::new();
+    let mut s = source.trim_start();
+    while !s.is_empty() {
+        s = match tolerant_object(source, s, None, &[], &mut diagnostics) {
+            Some((rest, object)) => {
+                objects.extend(object);
+                rest
+            }
+            None => {
+                diagnostics.push(diagnose_object(source, s, None));
+                skip_broken_object(s)
+            }
+        }
+        .trim_start();
+    }
+    Recovered {
+        objects: Objects(objects),
+        diagnostics,
+    }
+}
+
+/// Parses an object, recording the errors of its malformed parts. Returns `None` if the start
+/// line cannot be parsed, and no object if the object had to be skipped.
+///
+/// `ancestors` are the types of the enclosing objects. An `End` line of one of those types is
+/// left unconsumed, so that a missing end line only drops the unclosed object.
+fn tolerant_object<'a>(
+    source: &'a str,
+    s: &'a str,
+    parent: Option<&str>,
+    ancestors: &[&str],
+    diagnostics: &mut Vec<ParseError>,
+) -> Option<(&'a str, Option<Object>)> {
+    let (mut p, header) = object_begin(s).ok()?;
+    let name = header
+        .header_props
+        .iter()
+        .find(|p| p.key == "Name")
+        .and_then(|p| p.value.as_str())
+        .or(parent)
+        .map(str::to_owned);
+    let mut elements = Vec::new();
+    let mut malformed = false;
+    let mut types = ancestors.to_vec();
+    types.push(&header.object_type);
+    loop {
+        let q = multispace0::<_, ()>(p).map_or(p, |r| r.0);
+        if q.is_empty() {
+            let end = format!("`End {}`", header.object_type);
+            diagnostics.push(ParseError::new(source, q, name, end));
+            return Some((q, None));
+        }
+        if let Ok((rest, end)) = object_end(q) {
+            if end.object_type != header.object_type {
+                diagnostics.push(ParseError::new(
+                    source,
+                    q,
+                    name,
+                    format!("`End {}`", header.object_type),
+                ));
+                if ancestors.contains(&end.object_type.as_str()) {
+                    return Some((q, None));
+                }
+                return Some((rest, None));
+            }
+            let object = Object { header, elements };
+            return Some((rest, if malformed { None } else { Some(object) }));
+        }
+        if let Ok((rest, e)) = alt((
+            map(prop_kv, ObjectElement::Prop),
+            map(prop_custom_props, ObjectElement::CustomProp),
+        ))(q)
+        {
+            elements.push(e);
+            p = rest;
+            continue;
+        }
+        if q.starts_with("Begin ") {
+            p = match tolerant_object(source, q, name.as_deref(), &types, diagnostics) {
+                Some((rest, child)) => {
+                    elements.extend(child.map(ObjectElement::Object));
+                    rest
+                }
+                None => {
+                    diagnostics.push(diagnose_object(source, q, name.clone()));
+                    skip_broken_object(q)
+                }
+            };
+            continue;
+        }
+        diagnostics.push(diagnose_line(source, q, name.clone()));
+        malformed = true;
+        p = resync(q);
+    }
+}
+
+/// Skips the rest of the current line and any following line up to the next `Begin` or `End`
+/// line.
+fn resync(s: &str) -> &str {
+    let mut p = s;
+    loop {
+        p = match p.find('\n') {
+            Some(i) => &p[i + 1..],
+            None => return &p[p.len()..],
+        };
+        let line = p.trim_start();
+        if line.starts_with("Begin ") || line.starts_with("End ") {
+            return p;
+        }
+    }
+}
+
+/// Skips an object whose start line cannot be parsed, including its end line if it is found
+/// right after.
+fn skip_broken_object(s: &str) -> &str {
+    let object_type = s
+        .strip_prefix("Begin ")
+        .and_then(|t| t.split_whitespace().next())
+        .unwrap_or_default();
+    let p = resync(s);
+    match object_end(p.trim_start()) {
+        Ok((rest, end)) if end.object_type == object_type => rest,
+        _ => p,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recover_malformed_object() {
+        let sample = r#"Begin Object Class=/Script/BlueprintGraph.K2Node_VariableGet Name="A"
+   NodePosX=0
+End Object
+Begin Object Class=/Script/BlueprintGraph.K2Node_VariableGet Name="B"
+   NodePosX=?
+   NodePosY=0
+End Object
+Begin Object Class=/Script/BlueprintGraph.K2Node_VariableGet Name="C"
+   NodePosX=2
+End Object
+"#;
+        let r = objects_tolerant(sample);
+        let names: Vec<_> = r
+            .objects
+            .0
+            .iter()
+            .map(|o| o.header_prop("Name").and_then(|v| v.as_str()).unwrap())
+            .collect();
+        assert_eq!(names, ["A", "C"]);
+        assert_eq!(r.diagnostics.len(), 1);
+        let e = &r.diagnostics[0];
+        assert_eq!((e.line, e.column), (5, 13));
+        assert_eq!(e.object.as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn recover_nested_object() {
+        let sample = r#"Begin Map
+   Begin Level
+      Begin Actor Class=/Script/Engine.CameraActor Name="Camera"
+         Foo=?
+      End Actor
+      Begin Actor Class=/Script/Engine.CameraActor Name="Light"
+      End Actor
+   End Level
+End Map
+"#;
+        let r = objects_tolerant(sample);
+        assert_eq!(r.diagnostics.len(), 1);
+        assert_eq!(r.diagnostics[0].line, 4);
+        assert_eq!(r.diagnostics[0].object.as_deref(), Some("Camera"));
+        let level = match &r.objects.0[0].elements[..] {
+            [ObjectElement::Object(level)] => level,
+            e => panic!("unexpected elements: {:?}", e),
+        };
+        assert_eq!(level.elements.len(), 1);
+    }
+
+    #[test]
+    fn recover_missing_nested_end() {
+        let sample = r#"Begin Map
+   Begin Level
+      Begin Actor Class=/Script/Engine.CameraActor Name="Camera"
+         Begin Object Name="SceneComponent"
+            RelativeLocation=(X=1,Y=2,Z=3)
+      End Actor
+      Begin Actor Class=/Script/Engine.CameraActor Name="Light"
+      End Actor
+   End Level
+End Map
+"#;
+        let r = objects_tolerant(sample);
+        assert_eq!(r.diagnostics.len(), 1);
+        let e = &r.diagnostics[0];
+        assert_eq!((e.line, e.column), (6, 7));
+        assert_eq!(e.object.as_deref(), Some("SceneComponent"));
+        assert_eq!(e.expected, "`End Object`");
+        let level = match &r.objects.0[0].elements[..] {
+            [ObjectElement::Object(level)] => level,
+            e => panic!("unexpected elements: {:?}", e),
+        };
+        let actors: Vec<_> = level
+            .elements
+            .iter()
+            .map(|e| match e {
+                ObjectElement::Object(actor) => (
+                    actor.header_prop("Name").and_then(|v| v.as_str()).unwrap(),
+                    actor.elements.len(),
+                ),
+                e => panic!("unexpected element: {:?}", e),
+            })
+            .collect();
+        assert_eq!(actors, [("Camera", 0), ("Light", 0)]);
+    }
+
+    #[test]
+    fn recover_broken_structure() {
+        let sample = r#"garbage
+Begin Object Class=/Script/Engine.Foo Name="A"
+End Object
+Begin Object Class=
+End Object
+Begin Object Class=/Script/Engine.Foo Name="B"
+   NodePosX=1
+"#;
+        let r = objects_tolerant(sample);
+        assert_eq!(r.objects.0.len(), 1);
+        let lines: Vec<_> = r.diagnostics.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [1, 4, 8]);
+        assert_eq!(r.diagnostics[2].expected, "`End Object`");
+    }
+}
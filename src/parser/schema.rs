@@ -0,0 +1,94 @@
+use uuid::Uuid;
+
+use super::ast::PropValue;
+
+/// The kind of value a property is known to hold, for values whose spelling is ambiguous.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// An enum value, written as a bare identifier such as `EGPD_Output` or `None`.
+    Enum,
+    /// A GUID, written as 32 hex digits.
+    Guid,
+}
+
+/// Properties that Unreal writes with a known kind of value, by the last segment of their key.
+/// Keys ending in `Guid`, such as `NodeGuid` or `MemberGuid`, are GUIDs too.
+const KNOWN_PROPS: &[(&str, ValueKind)] = &[
+    ("PinId", ValueKind::Guid),
+    ("ContainerType", ValueKind::Enum),
+    ("Direction", ValueKind::Enum),
+    ("DesiredPinDirection", ValueKind::Enum),
+    ("Mobility", ValueKind::Enum),
+];
+
+/// Returns the kind of value the property is known to hold. Nested keys such as
+/// `PinType.ContainerType` are looked up by their last segment.
+pub fn value_kind(key: &str) -> Option<ValueKind> {
+    let name = key.rsplit('.').next().unwrap_or(key);
+    KNOWN_PROPS
+        .iter()
+        .find(|(k, _)| *k == name)
+        .map(|(_, kind)| *kind)
+        .or_else(|| name.ends_with("Guid").then_some(ValueKind::Guid))
+}
+
+/// Types a parsed value according to the property it belongs to.
+pub fn interpret(key: &str, value: PropValue) -> PropValue {
+    match (value_kind(key), value) {
+        (Some(ValueKind::Enum), PropValue::None) => PropValue::Enum("None".to_owned()),
+        (Some(ValueKind::Guid), PropValue::Other(v)) if v.len() == 32 => {
+            match Uuid::parse_str(&v) {
+                Ok(uuid) => PropValue::Uuid(uuid),
+                Err(_) => PropValue::Other(v),
+            }
+        }
+        (_, v) => v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ast::Prop, prop::prop_kv};
+
+    #[test]
+    fn interpret_by_key() {
+        assert_eq!(value_kind("PinType.ContainerType"), Some(ValueKind::Enum));
+        assert_eq!(value_kind("PinType.PinSubCategoryObject"), None);
+        assert_eq!(
+            interpret("PinType.ContainerType", PropValue::None),
+            PropValue::Enum("None".to_owned())
+        );
+        assert_eq!(
+            prop_kv("ContainerType=Array"),
+            Ok((
+                "",
+                Prop {
+                    key: "ContainerType".to_owned(),
+                    value: PropValue::Enum("Array".to_owned())
+                }
+            ))
+        );
+        assert_eq!(
+            interpret("PinType.PinSubCategoryObject", PropValue::None),
+            PropValue::None
+        );
+        let hex = "0710E8C14EFFED0DD9E024BCB29F23C3";
+        assert_eq!(
+            value_kind("MemberReference.MemberGuid"),
+            Some(ValueKind::Guid)
+        );
+        assert_eq!(
+            interpret("PersistentGuid", PropValue::Other(hex.to_owned())),
+            PropValue::Uuid(Uuid::parse_str(hex).unwrap())
+        );
+        assert_eq!(
+            interpret("Hash", PropValue::Other(hex.to_owned())),
+            PropValue::Other(hex.to_owned())
+        );
+        assert_eq!(
+            interpret("Direction", PropValue::String("EGPD_Output".to_owned())),
+            PropValue::String("EGPD_Output".to_owned())
+        );
+    }
+}
@@ -0,0 +1,521 @@
+use uuid::Uuid;
+
+use crate::parser::{
+    ast::{
+        CustomProp, CustomPropValue, LinkedTo, Object, ObjectElement, ObjectEnd, ObjectHeader,
+        Objects, Prop, PropValue, Text,
+    },
+    cst::{CstElement, CstObject, Document, Line},
+};
+
+/// The indentation of one nesting level of objects.
+pub const INDENT: &str = "   ";
+
+/// Escapes a string so that `string_literal` reads it back unchanged.
+pub fn escape_string(s: &str) -> String {
+    let mut r = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => r.push_str("\\\\"),
+            '\"' => r.push_str("\\\""),
+            '\'' => r.push_str("\\\'"),
+            '\n' => r.push_str("\\n"),
+            '\t' => r.push_str("\\t"),
+            '\r' => r.push_str("\\r"),
+            _ => r.push(c),
+        }
+    }
+    r
+}
+
+/// Writes a string literal.
+pub fn write_string_literal(s: &str) -> String {
+    format!("\"{}\"", escape_string(s))
+}
+
+/// Writes an uuid the way Unreal does (32 upper case hex digits).
+pub fn write_uuid(uuid: &Uuid) -> String {
+    format!("{:X}", uuid.to_simple_ref())
+}
+
+/// Writes a literal representing a node's connection destination.
+pub fn write_linked_object(l: &LinkedTo) -> String {
+    format!("{} {}", l.name, write_uuid(&l.uuid))
+}
+
+/// Writes a list of key / value properties, optionally followed by a trailing comma.
+pub fn write_kv_list(props: &[Prop], trailing_comma: bool) -> String {
+    if props.is_empty() {
+        return "()".to_owned();
+    }
+    let list = props.iter().map(write_prop).collect::<Vec<_>>().join(",");
+    if trailing_comma {
+        format!("({},)", list)
+    } else {
+        format!("({})", list)
+    }
+}
+
+/// Writes a text literal.
+pub fn write_text(t: &Text) -> String {
+    let (name, args) = match t {
+        Text::NsLoc {
+            namespace,
+            key,
+            source,
+        } => ("NSLOCTEXT", vec![namespace, key, source]),
+        Text::Loc { key, source } => ("LOCTEXT", vec![key, source]),
+        Text::Invariant(source) => ("INVTEXT", vec![source]),
+        Text::LocTable { table, key } => ("LOCTABLE", vec![table, key]),
+        Text::Generated { generator, args } => {
+            let args = args.iter().map(write_prop_value).collect::<Vec<_>>();
+            return format!("{}({})", generator, args.join(", "));
+        }
+    };
+    let args = args.into_iter().map(|a| write_string_literal(a));
+    format!("{}({})", name, args.collect::<Vec<_>>().join(", "))
+}
+
+/// Writes any PropValue of an object.
+pub fn write_prop_value(v: &PropValue) -> String {
+    match v {
+        PropValue::String(v) => write_string_literal(v),
+        PropValue::Integer(v) => v.to_string(),
+        PropValue::Double(v) => v.text().to_owned(),
+        PropValue::Boolean(v) => if *v { "True" } else { "False" }.to_owned(),
+        PropValue::None => "None".to_owned(),
+        PropValue::Enum(v) => v.to_owned(),
+        PropValue::Uuid(v) => write_uuid(v),
+        PropValue::Text(v) => write_text(v),
+        PropValue::ObjectReference { class, path } => {
+            format!("{}'{}'", class, write_string_literal(path.as_str()))
+        }
+        PropValue::LinkedToList(v) if v.is_empty() => "()".to_owned(),
+        PropValue::LinkedToList(v) => format!(
+            "({},)",
+            v.iter()
+                .map(write_linked_object)
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        PropValue::PropList(v) => write_kv_list(v, false),
+        PropValue::Array(v) => format!(
+            "({})",
+            v.iter().map(write_prop_value).collect::<Vec<_>>().join(",")
+        ),
+        PropValue::StaticArray(v) => format!(
+            "({})",
+            v.iter()
+                .map(|e| write_prop_value(&e.value))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        PropValue::Other(v) => v.to_owned(),
+    }
+}
+
+/// Writes the `Key=Value` entries of a property. A static array gives one `Key(i)=Value` entry
+/// per element.
+pub fn write_prop_entries(p: &Prop) -> Vec<String> {
+    match &p.value {
+        PropValue::StaticArray(v) => v
+            .iter()
+            .map(|e| format!("{}({})={}", p.key, e.index, write_prop_value(&e.value)))
+            .collect(),
+        v => vec![format!("{}={}", p.key, write_prop_value(v))],
+    }
+}
+
+/// Writes a Key / Value property. The entries of a static array are separated by commas.
+pub fn write_prop(p: &Prop) -> String {
+    write_prop_entries(p).join(",")
+}
+
+/// Writes a CustomProperties line without indentation.
+pub fn write_custom_prop(c: &CustomProp) -> String {
+    let value = match &c.value {
+        CustomPropValue::Pin(v) => write_kv_list(v, true),
+        CustomPropValue::UserDefinedPin(v) => write_kv_list(v, false),
+        CustomPropValue::Other(v) => write_prop_value(v),
+    };
+    if value.is_empty() {
+        format!("CustomProperties {}", c.domain)
+    } else {
+        format!("CustomProperties {} {}", c.domain, value)
+    }
+}
+
+/// Writes an object start line without indentation and line ending.
+pub fn write_object_begin(h: &ObjectHeader) -> String {
+    let mut r = format!("Begin {}", h.object_type);
+    for p in &h.header_props {
+        r.push(' ');
+        match (p.key.as_str(), &p.value) {
+            ("Class", PropValue::String(class)) => {
+                r.push_str("Class=");
+                r.push_str(class);
+            }
+            ("ExportPath", PropValue::ObjectReference { class, path }) => {
+                r.push_str(&format!("ExportPath=\"{}'{}'\"", class, path.as_str()));
+            }
+            _ => r.push_str(&write_prop(p)),
+        }
+    }
+    r
+}
+
+/// Writes an object end line without indentation and line ending.
+pub fn write_object_end(e: &ObjectEnd) -> String {
+    format!("End {}", e.object_type)
+}
+
+/// Writes an element of an object at the given nesting depth.
+pub fn write_object_element(e: &ObjectElement, depth: usize, out: &mut String) {
+    match e {
+        ObjectElement::Prop(p) => {
+            for entry in write_prop_entries(p) {
+                write_line(&entry, depth, out);
+            }
+        }
+        ObjectElement::CustomProp(c) => write_line(&write_custom_prop(c), depth, out),
+        ObjectElement::Object(o) => write_object(o, depth, out),
+    }
+}
+
+/// Writes a single object at the given nesting depth.
+pub fn write_object(o: &Object, depth: usize, out: &mut String) {
+    write_line(&write_object_begin(&o.header), depth, out);
+    for e in &o.elements {
+        write_object_element(e, depth + 1, out);
+    }
+    write_line(
+        &write_object_end(&ObjectEnd {
+            object_type: o.header.object_type.clone(),
+        }),
+        depth,
+        out,
+    );
+}
+
+/// Writes multiple objects in the format used by the Unreal editor's clipboard.
+pub fn write_objects(objects: &Objects) -> String {
+    let mut out = String::new();
+    for o in &objects.0 {
+        write_object(o, 0, &mut out);
+    }
+    out
+}
+
+/// Writes a single lossless object at the given nesting depth.
+pub fn write_cst_object(o: &CstObject, depth: usize, line_ending: &str, out: &mut String) {
+    write_cst_object_indented(o, &INDENT.repeat(depth), line_ending, out);
+}
+
+/// Writes a lossless object whose new lines are indented with `indent`. New lines inside the
+/// object take the indentation of their siblings, so inserted lines line up with parsed ones.
+fn write_cst_object_indented(o: &CstObject, indent: &str, line_ending: &str, out: &mut String) {
+    let child_indent = o
+        .elements
+        .iter()
+        .find_map(|e| match e {
+            CstElement::Prop(l) => line_indent(l),
+            CstElement::CustomProp(l) => line_indent(l),
+            CstElement::Object(o) => line_indent(&o.begin),
+        })
+        .map_or_else(|| format!("{}{}", indent, INDENT), str::to_owned);
+    write_cst_line(&o.begin, write_object_begin, indent, line_ending, out);
+    for e in &o.elements {
+        match e {
+            CstElement::Prop(l) => {
+                // An edited static array is written with one entry per line.
+                let separator =
+                    format!("{}{}", line_ending, line_indent(l).unwrap_or(&child_indent));
+                let write = |p: &Prop| write_prop_entries(p).join(&separator);
+                write_cst_line(l, write, &child_indent, line_ending, out)
+            }
+            CstElement::CustomProp(l) => {
+                write_cst_line(l, write_custom_prop, &child_indent, line_ending, out)
+            }
+            CstElement::Object(o) => write_cst_object_indented(o, &child_indent, line_ending, out),
+        }
+    }
+    write_cst_line(&o.end, write_object_end, indent, line_ending, out);
+}
+
+/// Writes a lossless document.
+///
+/// Unmodified lines are written exactly as they were parsed, so writing an unmodified document
+/// reproduces its source byte for byte.
+pub fn write_document(doc: &Document) -> String {
+    let mut out = String::new();
+    for o in &doc.objects {
+        write_cst_object(o, 0, &doc.line_ending, &mut out);
+    }
+    out.push_str(&doc.trailing);
+    out
+}
+
+/// Returns the indentation a parsed line was written with.
+fn line_indent<T>(line: &Line<T>) -> Option<&str> {
+    line.trivia
+        .as_ref()
+        .map(|t| t.leading.rsplit('\n').next().unwrap_or_default())
+}
+
+fn write_cst_line<T: Clone + PartialEq>(
+    line: &Line<T>,
+    write: impl Fn(&T) -> String,
+    indent: &str,
+    line_ending: &str,
+    out: &mut String,
+) {
+    match &line.trivia {
+        Some(t) => out.push_str(&t.leading),
+        None => out.push_str(indent),
+    }
+    match line.source_text() {
+        Some(text) => out.push_str(text),
+        None => out.push_str(&write(&line.value)),
+    }
+    match &line.trivia {
+        Some(t) => out.push_str(&t.trailing),
+        None => out.push_str(line_ending),
+    }
+}
+
+fn write_line(line: &str, depth: usize, out: &mut String) {
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+    out.push_str(line);
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::{
+            ast::{Double, ObjectHeader, ObjectPath, StaticArrayElement},
+            literal::string_literal,
+            lossless::document,
+            prop::prop_custom_props,
+        },
+        read_document, read_object,
+    };
+
+    #[test]
+    fn write_escaped_string() {
+        let s = "hoge\n \r \t\\ ' f\"uga";
+        assert_eq!(write_string_literal(s), r#""hoge\n \r \t\\ \' f\"uga""#);
+        assert_eq!(
+            string_literal(&write_string_literal(s)),
+            Ok(("", s.to_owned()))
+        );
+        assert_eq!(write_string_literal(""), r#""""#);
+    }
+
+    #[test]
+    fn write_values() {
+        let uuid = Uuid::parse_str("570BAD4542CBB0285413EEAB4F6DBDDA").unwrap();
+        assert_eq!(write_prop_value(&PropValue::Integer(-16)), "-16");
+        assert_eq!(
+            write_prop_value(&PropValue::Double(Double::new(-560.0).unwrap())),
+            "-560.000000"
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::Double(Double::new(0.1234567891).unwrap())),
+            "0.1234567891"
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::Double(Double::new(1e-7).unwrap())),
+            "1e-7"
+        );
+        assert_eq!(Double::new(f64::NAN), None);
+        assert_eq!(Double::new(f64::INFINITY), None);
+        assert_eq!(write_prop_value(&PropValue::Boolean(true)), "True");
+        assert_eq!(
+            write_prop_value(&PropValue::Uuid(uuid)),
+            "570BAD4542CBB0285413EEAB4F6DBDDA"
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::Text(Text::NsLoc {
+                namespace: "K2Node".to_owned(),
+                key: "Target".to_owned(),
+                source: "Target".to_owned()
+            })),
+            r#"NSLOCTEXT("K2Node", "Target", "Target")"#
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::Text(Text::Generated {
+                generator: "LOCGEN_NUMBER".to_owned(),
+                args: vec![PropValue::Integer(10), PropValue::String("".to_owned())]
+            })),
+            r#"LOCGEN_NUMBER(10, "")"#
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::ObjectReference {
+                class: "Class".to_owned(),
+                path: ObjectPath::from("/Script/UMG.Button")
+            }),
+            r#"Class'"/Script/UMG.Button"'"#
+        );
+        assert_eq!(write_prop_value(&PropValue::None), "None");
+        assert_eq!(
+            write_prop_value(&PropValue::Enum("EGPD_Output".to_owned())),
+            "EGPD_Output"
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::LinkedToList(vec![LinkedTo {
+                name: "K2Node_VariableGet_17".to_owned(),
+                uuid
+            }])),
+            "(K2Node_VariableGet_17 570BAD4542CBB0285413EEAB4F6DBDDA,)"
+        );
+        assert_eq!(write_prop_value(&PropValue::PropList(Vec::new())), "()");
+        assert_eq!(
+            write_prop_value(&PropValue::Array(vec![
+                PropValue::String("A".to_owned()),
+                PropValue::Array(vec![PropValue::Integer(1), PropValue::Integer(2)])
+            ])),
+            r#"("A",(1,2))"#
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::PropList(vec![
+                Prop {
+                    key: "MemberName".to_owned(),
+                    value: PropValue::String("StartButton".to_owned())
+                },
+                Prop {
+                    key: "bSelfContext".to_owned(),
+                    value: PropValue::Boolean(true)
+                }
+            ])),
+            r#"(MemberName="StartButton",bSelfContext=True)"#
+        );
+        assert_eq!(
+            write_prop_value(&PropValue::Other("NotSelfContext".to_owned())),
+            "NotSelfContext"
+        );
+    }
+
+    #[test]
+    fn write_custom_props() {
+        for code in [
+            r#"CustomProperties UserDefinedPin (PinName="Speed",PinType=(PinCategory="float"))"#,
+            "CustomProperties MaterialExpression (Input=1,Output=2)",
+            "CustomProperties Anim_State Entry -> Idle",
+            "CustomProperties Empty",
+        ] {
+            let (_, c) = prop_custom_props(code).unwrap();
+            assert_eq!(write_custom_prop(&c), code);
+        }
+    }
+
+    #[test]
+    fn write_object_text() {
+        let sample = r#"Begin Object Class=/Script/BlueprintGraph.K2Node_VariableGet Name="K2Node_VariableGet_1"
+   NodePosX=512
+   CustomProperties Pin (PinId=7CD635904148E54F000DA597BA60AB39,LinkedTo=(K2Node_VariableGet_17 570BAD4542CBB0285413EEAB4F6DBDDA,),)
+   Begin Object Name="SceneComponent"
+   End Object
+End Object
+"#;
+        assert_eq!(write_objects(&read_object(sample).unwrap()), sample);
+
+        let sample = "Begin Object Name=\"Foo.Bar\" ExportPath=\"Class'/Pkg/A.A:B'\"\nEnd Object\n";
+        assert_eq!(write_objects(&read_object(sample).unwrap()), sample);
+    }
+
+    const FIXTURES: [&str; 16] = [
+        include_str!("../k2node_codes/awesome_function.bp"),
+        include_str!("../k2node_codes/call_function.bp"),
+        include_str!("../k2node_codes/camera.bp"),
+        include_str!("../k2node_codes/comment.bp"),
+        include_str!("../k2node_codes/custom_event.bp"),
+        include_str!("../k2node_codes/empty.bp"),
+        include_str!("../k2node_codes/input_axis_event.bp"),
+        include_str!("../k2node_codes/knot.bp"),
+        include_str!("../k2node_codes/knot_fan_out.bp"),
+        include_str!("../k2node_codes/name_comma_function.bp"),
+        include_str!("../k2node_codes/test1.bp"),
+        include_str!("../k2node_codes/test2.bp"),
+        include_str!("../k2node_codes/test3.txt"),
+        include_str!("../k2node_codes/two_nodes.bp"),
+        include_str!("../k2node_codes/variable_get_float.bp"),
+        include_str!("../k2node_codes/variable_get_object.bp"),
+    ];
+
+    #[test]
+    fn round_trip_fixtures() {
+        for code in FIXTURES {
+            let objects = read_object(code).unwrap();
+            assert_eq!(read_object(&write_objects(&objects)).unwrap(), objects);
+        }
+    }
+
+    #[test]
+    fn lossless_round_trip() {
+        for code in FIXTURES {
+            assert_eq!(write_document(&document(code).unwrap().1), code);
+            let crlf = code.replace('\n', "\r\n");
+            assert_eq!(write_document(&document(&crlf).unwrap().1), crlf);
+        }
+        let code = "Begin Object Name=\"A\"\n\tX=(A=1,B=2,) \n\n   Y=-560.000000\nEnd Object\n\n\n";
+        assert_eq!(write_document(&document(code).unwrap().1), code);
+    }
+
+    #[test]
+    fn lossless_edit() {
+        let code = "Begin Object Name=\"A\"\r\n  X=(A=1,B=2,)\r\n  Y=-560.000000\r\nEnd Object\r\n";
+        let mut doc = document(code).unwrap().1;
+        let o = &mut doc.objects[0];
+        if let CstElement::Prop(l) = &mut o.elements[1] {
+            l.value.value = PropValue::Integer(3);
+        }
+        o.elements.push(CstElement::Prop(Line::new(Prop {
+            key: "Z".to_owned(),
+            value: PropValue::Boolean(true),
+        })));
+        assert_eq!(
+            write_document(&doc),
+            "Begin Object Name=\"A\"\r\n  X=(A=1,B=2,)\r\n  Y=3\r\n  Z=True\r\nEnd Object\r\n"
+        );
+
+        let mut doc = document(code).unwrap().1;
+        if let CstElement::Prop(l) = &mut doc.objects[0].elements[1] {
+            l.value.value = PropValue::StaticArray(vec![
+                StaticArrayElement {
+                    index: 0,
+                    value: PropValue::Integer(5),
+                },
+                StaticArrayElement {
+                    index: 1,
+                    value: PropValue::Integer(6),
+                },
+            ]);
+        }
+        let written = write_document(&doc);
+        assert_eq!(
+            written,
+            "Begin Object Name=\"A\"\r\n  X=(A=1,B=2,)\r\n  Y(0)=5\r\n  Y(1)=6\r\nEnd Object\r\n"
+        );
+        assert_eq!(write_document(&read_document(&written).unwrap()), written);
+
+        let doc = Document::from(&read_object(code).unwrap());
+        assert_eq!(
+            write_document(&doc),
+            "Begin Object Name=\"A\"\n   X=(A=1,B=2)\n   Y=-560.000000\nEnd Object\n"
+        );
+        assert_eq!(
+            doc.objects[0].begin.value,
+            ObjectHeader {
+                object_type: "Object".to_owned(),
+                header_props: vec![Prop {
+                    key: "Name".to_owned(),
+                    value: PropValue::String("A".to_owned())
+                }]
+            }
+        );
+    }
+}